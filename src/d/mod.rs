@@ -1,5 +1,5 @@
 use super::Position;
-use super::position::piece::Piece;
+use super::position::{bitboard::Squares, piece::Piece, to_square};
 
 pub fn display(position: &Position) {
     print!("\n ┌───┬───┬───┬───┬───┬───┬───┬───┐\n │");
@@ -36,7 +36,8 @@ pub fn display(position: &Position) {
     }
     print!(" 1\n └───┴───┴───┴───┴───┴───┴───┴───┘\n");
     println!("   a   b   c   d   e   f   g   h");
-    println!("\nFen: {}", position.fen);
-    // println!("Key: ");
-    println!("Checkers: ");
+    println!("\nFen: {}", position.fen());
+    println!("Key: {:016X}", position.key);
+    let checkers: Vec<String> = Squares(position.checkers()).map(|square| to_square(&square)).collect::<Vec<String>>();
+    println!("Checkers: {}", checkers.join(" "));
 }
\ No newline at end of file
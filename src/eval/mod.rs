@@ -0,0 +1,186 @@
+pub mod pst;
+
+use super::position::{bitboard::{self, Bitboard, Bitboards, Squares, KNIGHT_ATTACKS, PAWN_ATTACKS}, Position};
+
+// Per safe square reached, counted from a typical number of squares so an average piece scores 0.
+// Indexed by piece type - 2, knights to queens.
+const MOBILITY_WEIGHTS: [Score; 4] = [Score { mg: 4, eg: 4 }, Score { mg: 5, eg: 5 }, Score { mg: 2, eg: 4 }, Score { mg: 1, eg: 2 }];
+const MOBILITY_AVERAGES: [i32; 4] = [4, 6, 7, 13];
+
+// A middlegame and an endgame value, blended by the game phase at the end
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Score {
+    pub mg: i32,
+    pub eg: i32,
+}
+
+impl Score {
+    pub fn add(&mut self, mg: i32, eg: i32) {
+        self.mg += mg;
+        self.eg += eg;
+    }
+}
+
+// Every term is kept per color so the eval command can show where the score comes from
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Evaluation {
+    pub material: [Score; 2], // Indexed by color, 0 = WHITE and 1 = BLACK
+    pub psqt: [Score; 2],
+    pub mobility: [Score; 2],
+    pub phase: i32,
+}
+
+impl Evaluation {
+    pub fn terms(&self) -> [(&'static str, [Score; 2]); 3] {
+        return [
+            ("Material", self.material),
+            ("PSQT", self.psqt),
+            ("Mobility", self.mobility),
+        ];
+    }
+
+    // WHITE's point of view, summed over the terms and tapered between middlegame and endgame
+    pub fn total(&self) -> i32 {
+        let mut total: Score = Score::default();
+        for (_, term) in self.terms() {
+            total.add(term[0].mg - term[1].mg, term[0].eg - term[1].eg);
+        }
+        return taper(total, self.phase);
+    }
+}
+
+pub fn taper(score: Score, phase: i32) -> i32 {
+    let phase: i32 = phase.min(pst::MAX_PHASE);
+    return (score.mg * phase + score.eg * (pst::MAX_PHASE - phase)) / pst::MAX_PHASE;
+}
+
+pub fn breakdown(position: &Position) -> Evaluation {
+    let mut evaluation: Evaluation = Evaluation::default();
+    for (square, piece) in position.state.board.iter().enumerate() {
+        let piece_type: usize = (piece.data & 0b111) as usize;
+        if(piece_type == 0) {
+            continue;
+        }
+        let color: usize = ((piece.data & 0b1000) >> 3) as usize;
+        let table_index: usize = match(color) {
+            0 => square ^ 56,
+            _ => square,
+        };
+        evaluation.material[color].add(pst::MIDDLEGAME_VALUES[piece_type - 1], pst::ENDGAME_VALUES[piece_type - 1]);
+        evaluation.psqt[color].add(pst::MIDDLEGAME_TABLES[piece_type - 1][table_index], pst::ENDGAME_TABLES[piece_type - 1][table_index]);
+        evaluation.phase += pst::PHASE_WEIGHTS[piece_type - 1];
+    }
+    for color in [false, true] {
+        evaluation.mobility[color as usize] = mobility(position, color);
+    }
+    return evaluation;
+}
+
+// Squares a piece attacks that aren't held by its own side nor covered by an enemy pawn
+fn mobility(position: &Position, color: bool) -> Score {
+    let bitboards: &Bitboards = &position.bitboards;
+    let occupied: Bitboard = bitboards.occupied();
+    let mut enemy_pawn_attacks: Bitboard = 0;
+    for square in Squares(bitboards.of(0b001, !color)) {
+        enemy_pawn_attacks |= PAWN_ATTACKS[!color as usize][square as usize];
+    }
+    let area: Bitboard = !bitboards.colors[color as usize] & !enemy_pawn_attacks;
+
+    let mut score: Score = Score::default();
+    for piece_type in 0b010..=0b101 {
+        let index: usize = piece_type as usize - 2;
+        for square in Squares(bitboards.of(piece_type, color)) {
+            let attacks: Bitboard = match(piece_type) {
+                0b010 => KNIGHT_ATTACKS[square as usize],
+                0b011 => bitboard::bishop_attacks(square, occupied),
+                0b100 => bitboard::rook_attacks(square, occupied),
+                _ => bitboard::queen_attacks(square, occupied),
+            };
+            let squares: i32 = bitboard::popcount(attacks & area) as i32 - MOBILITY_AVERAGES[index];
+            score.add(MOBILITY_WEIGHTS[index].mg * squares, MOBILITY_WEIGHTS[index].eg * squares);
+        }
+    }
+    return score;
+}
+
+// Centipawns from the side to move's point of view
+pub fn evaluate(position: &Position) -> i32 {
+    let total: i32 = breakdown(position).total();
+    return match(position.state.color) {
+        false => total,
+        true => -total,
+    };
+}
+
+pub fn print_eval(position: &Position) {
+    let evaluation: Evaluation = breakdown(position);
+    let format_pawns = |value: i32| -> String { format!("{:6.2}", value as f64 / 100.0) };
+
+    println!("      Term    |    White    |    Black    |    Total");
+    println!("              |   MG    EG  |   MG    EG  |   MG    EG");
+    println!(" -------------+-------------+-------------+------------");
+    let mut total: Score = Score::default();
+    for (name, term) in evaluation.terms() {
+        println!(
+            " {:>12} |{}{} |{}{} |{}{}",
+            name,
+            format_pawns(term[0].mg), format_pawns(term[0].eg),
+            format_pawns(term[1].mg), format_pawns(term[1].eg),
+            format_pawns(term[0].mg - term[1].mg), format_pawns(term[0].eg - term[1].eg),
+        );
+        total.add(term[0].mg - term[1].mg, term[0].eg - term[1].eg);
+    }
+    println!(" -------------+-------------+-------------+------------");
+    println!(" {:>12} |             |             |{}{}", "Total", format_pawns(total.mg), format_pawns(total.eg));
+    println!();
+    println!("Phase: {}/{}", evaluation.phase.min(pst::MAX_PHASE), pst::MAX_PHASE);
+    println!("Final evaluation: {:+.2} (white side)", evaluation.total() as f64 / 100.0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::position::fen::START_FEN;
+
+    // Same position with the colors swapped: ranks reversed, piece colors and the side to move switched
+    fn mirror(fen: &str) -> String {
+        let fields: Vec<&str> = fen.split_whitespace().collect::<Vec<&str>>();
+        let swap_case = |text: &str| -> String {
+            text.chars().map(|c| if(c.is_ascii_uppercase()) { c.to_ascii_lowercase() } else { c.to_ascii_uppercase() }).collect::<String>()
+        };
+        let board: String = swap_case(&fields[0].split('/').rev().collect::<Vec<&str>>().join("/"));
+        let color: &str = if(fields[1] == "w") { "b" } else { "w" };
+        let mut castle: Vec<char> = swap_case(fields[2]).chars().collect::<Vec<char>>();
+        castle.sort_by_key(|&c| "KQkq-".find(c));
+        let en_passant: String = fields[3].replace('3', "x").replace('6', "3").replace('x', "6");
+        return format!("{} {} {} {} {} {}", board, color, castle.iter().collect::<String>(), en_passant, fields[4], fields[5]);
+    }
+
+    #[test]
+    fn scores_the_start_position_even() {
+        let position: Position = Position::from(START_FEN);
+        assert_eq!(evaluate(&position), 0);
+        assert_eq!(breakdown(&position).total(), 0);
+    }
+
+    #[test]
+    fn scores_mirrored_positions_alike() {
+        let fens: [&str; 6] = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4KQ2 b - - 0 1",
+            "2r3k1/1q3ppp/p3p3/1p1nP3/3N4/P5Q1/1P3PPP/2R3K1 w - - 3 25",
+        ];
+        for fen in fens {
+            let position: Position = Position::from(fen);
+            let mirrored: Position = Position::from(&mirror(fen));
+            assert_eq!(evaluate(&position), evaluate(&mirrored), "{} and {}", fen, mirror(fen));
+            assert_eq!(breakdown(&position).total(), -breakdown(&mirrored).total(), "{}", fen);
+        }
+        // A lone extra queen is worth about a queen to whoever has it
+        let queen_up: Position = Position::from("4k3/8/8/8/8/8/8/4KQ2 b - - 0 1");
+        assert!(evaluate(&queen_up) < -800);
+    }
+}
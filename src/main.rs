@@ -1,83 +1,245 @@
 #![allow(unused_parens)]
+#![allow(clippy::needless_return, clippy::collapsible_if, clippy::collapsible_else_if)]
 
 pub mod d;
+pub mod eval;
+pub mod pgn;
 pub mod position;
+pub mod search;
+pub mod uci;
+
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
 
 use d::display;
-use position::Position;
+use pgn::{reader::PgnReader, Game};
+use position::{error::PositionError, moves::Move, result::GameResult, Position};
+use search::{tt::TranspositionTable, Signals};
+use uci::{GoParams, Options};
 
 fn main() -> std::process::ExitCode {
     let mut x: String = String::new();
     let mut current_position: Position = Position::new();
+    let mut options: Options = Options::new();
+    let mut debug_mode: bool = false;
+    let signals: Arc<Signals> = Arc::new(Signals::new());
+    let tt: Arc<Mutex<TranspositionTable>> = Arc::new(Mutex::new(TranspositionTable::new(options.hash)));
+    let mut search_thread: Option<std::thread::JoinHandle<()>> = None;
     current_position.state.to_string();
     println!("TSMChess by TSM Studios");
+    position::magic::init();
     loop {
-        std::io::stdin()
+        let bytes_read: usize = std::io::stdin()
             .read_line(&mut x)
             .expect("Unable to read command");
-        let vec_x: Vec<&str> = x.split(" ")
-            .collect::<Vec<&str>>();
-        let cleaned_vec: Vec<String> = vec_x.iter()
-            .map(|&s| s.replace("\r", "").replace("\n", ""))
+        if(bytes_read == 0) {
+            // stdin was closed by the GUI, behave as if "quit" was sent
+            stop_search(&signals, &mut search_thread);
+            return std::process::ExitCode::SUCCESS;
+        }
+        let cleaned_vec: Vec<String> = x.split_whitespace()
+            .map(|s| s.to_string())
             .collect::<Vec<String>>();
+        if(cleaned_vec.is_empty()) {
+            x = String::new();
+            continue;
+        }
         let cmd: &str = &cleaned_vec[0];
         match(cmd) {
-            "position" => {
-                let move_start_index: usize;
-                if(&cleaned_vec[1] == "fen") {
-                    let fen_string = &cleaned_vec[2..=7];
-                    current_position = Position::from(&fen_string.join(" "));
-                    move_start_index = 8;
-                } else {
-                    current_position = Position::new();
-                    move_start_index = 2;
-                }
-
-                if((&cleaned_vec).len() > move_start_index) {
-                    if(&cleaned_vec[move_start_index] == "moves") {
-                        for proposed_move in &cleaned_vec[(move_start_index + 1)..] {
-                            if(proposed_move == &String::from("!")) {
-                                match(current_position.unmake_move()) {
-                                    Ok(_) => (),
-                                    Err(e) => return std::process::ExitCode::from(e),
-                                };
-                                continue;
-                            }
-                            let mut move_parts: Vec<String> = proposed_move.chars()
-                                .collect::<Vec<char>>()
-                                .chunks(2)
-                                .map(|chunk| chunk.iter().collect())
-                                .collect();
-
-                            if(move_parts.len() <= 2) {
-                                move_parts.push(String::new());
-                            }
-
-                            match(current_position.make_move(&move_parts[0], &move_parts[1], &move_parts[2])) {
-                                Ok(_) => (),
-                                Err(e) => return std::process::ExitCode::from(e),
+            "uci" => {
+                uci::print_id();
+            }
+            "isready" => {
+                println!("readyok");
+            }
+            "ucinewgame" => {
+                // The search thread holds the table until it's done, waiting on it would stop us reading "stop"
+                stop_search(&signals, &mut search_thread);
+                current_position = Position::new();
+                tt.lock().expect("transposition table poisoned").clear();
+            }
+            "setoption" => {
+                match(uci::parse_setoption(&cleaned_vec[1..])) {
+                    Some((name, value)) => {
+                        match(options.set(&name, &value)) {
+                            Ok(option_name) => {
+                                if(option_name == "Hash" || option_name == "Clear Hash") {
+                                    stop_search(&signals, &mut search_thread);
+                                }
+                                match(option_name) {
+                                    "Hash" => tt.lock().expect("transposition table poisoned").resize(options.hash),
+                                    "Clear Hash" => tt.lock().expect("transposition table poisoned").clear(),
+                                    _ => (),
+                                }
+                                if(debug_mode) {
+                                    println!("info string {} set to '{}'", name, value);
+                                }
                             }
+                            Err(e) => println!("info string {}", e),
                         }
                     }
+                    None => println!("info string Malformed setoption command"),
+                }
+            }
+            "position" => {
+                if let Err(e) = apply_position_command(&mut current_position, &cleaned_vec[1..]) {
+                    println!("info string {}", e);
+                }
+                let result: GameResult = current_position.game_result();
+                if(!current_position.history.is_empty() && result != GameResult::Ongoing) {
+                    println!("info string Game over: {} {}", result.score(current_position.state.color), result);
+                }
+            }
+            "go" => {
+                let params: GoParams = GoParams::parse(&cleaned_vec[1..]);
+                if let Some(depth) = params.perft {
+                    position::perft::print_divide(&mut current_position, depth);
+                    x = String::new();
+                    continue;
+                }
+                stop_search(&signals, &mut search_thread);
+                search_thread = Some(search::start(current_position.clone(), &params, &options, tt.clone(), signals.clone()));
+            }
+            "stop" => {
+                stop_search(&signals, &mut search_thread);
+            }
+            "ponderhit" => {
+                // The pondered move was played, the search now finishes like a normal one
+                signals.ponder.store(false, Ordering::SeqCst);
+            }
+            "divide" => {
+                match(cleaned_vec.get(1).and_then(|s| s.parse::<u32>().ok())) {
+                    Some(depth) => position::perft::print_divide(&mut current_position, depth),
+                    None => println!("info string Usage: divide <depth>"),
                 }
             }
+            "eval" => {
+                eval::print_eval(&current_position);
+            }
             "d" => {
                 display(&current_position);
             }
+            "pgn" => {
+                match(cleaned_vec.get(1)) {
+                    Some(path) => {
+                        let index: usize = cleaned_vec.get(2).and_then(|s| s.parse::<usize>().ok()).unwrap_or(1);
+                        match(load_pgn_game(path, index)) {
+                            Ok(game) => {
+                                current_position = game.end_position();
+                                println!(
+                                    "info string Loaded game {}: {} - {} {}, {} plies",
+                                    index,
+                                    game.tag("White").unwrap_or("?"),
+                                    game.tag("Black").unwrap_or("?"),
+                                    game.result,
+                                    game.moves.len()
+                                );
+                            }
+                            Err(e) => println!("info string {}", e),
+                        }
+                    }
+                    None => print!("{}", Game::from_position(&current_position)),
+                }
+            }
             "debug" => {
-                match(cleaned_vec[1].as_str()) {
-                    "gen" => {
+                match(cleaned_vec.get(1).map(|s| s.as_str())) {
+                    Some("on") => debug_mode = true,
+                    Some("off") => debug_mode = false,
+                    Some("gen") => {
                         dbg!(current_position.generate_legal_moves());
-                        ()
+                    }
+                    Some("see") => {
+                        match(cleaned_vec.get(2).map(|s| Move::from_uci(s, &current_position))) {
+                            Some(Ok(chess_move)) => println!("SEE {}: {}", chess_move.to_san(&current_position), position::see::see(&current_position, chess_move)),
+                            Some(Err(e)) => println!("info string {}", e),
+                            None => println!("info string Usage: debug see <move>"),
+                        }
                     }
                     _ => ()
                 };
             }
-            "quit" | "exit" => return std::process::ExitCode::SUCCESS,
+            // TSMChess never asks for registration, so whatever the GUI sends is ignored
+            "register" => (),
+            "quit" | "exit" => {
+                stop_search(&signals, &mut search_thread);
+                return std::process::ExitCode::SUCCESS;
+            }
             _ => {
                 println!("Unknown command: '{}'. Type help for more information.", cmd);
             }
         }
         x = String::new();
     }
-}
\ No newline at end of file
+}
+
+// Handles "position [startpos | fen <fen>] [moves <move> ...]". A bad FEN leaves the position untouched,
+// a bad move leaves it after the last good one. "!" takes back the previous move and ">" plays it again.
+fn apply_position_command(position: &mut Position, tokens: &[String]) -> Result<(), PositionError> {
+    let moves_index: usize = tokens.iter().position(|token| token == "moves").unwrap_or(tokens.len());
+    match(tokens.first().map(|s| s.as_str())) {
+        Some("fen") => *position = Position::from_fen(&tokens[1..moves_index].join(" "))?,
+        Some("startpos") => *position = Position::new(),
+        source => return Err(PositionError::UnknownPositionSource(source.unwrap_or("").to_string())),
+    }
+
+    for proposed_move in tokens.iter().skip(moves_index + 1) {
+        match(proposed_move.as_str()) {
+            "!" => position.unmake_move()?,
+            ">" => position.redo_move()?,
+            _ => {
+                let chess_move: Move = Move::from_uci(proposed_move, position)?;
+                position.make_move(chess_move)?;
+            }
+        }
+    }
+    return Ok(());
+}
+
+// Streams through the file up to the index-th game (counting from 1), earlier games are parsed and dropped
+fn load_pgn_game(path: &str, index: usize) -> Result<Game, String> {
+    let file: std::fs::File = std::fs::File::open(path).map_err(|e| format!("Unable to open {}: {}", path, e))?;
+    let mut reader: PgnReader<std::io::BufReader<std::fs::File>> = PgnReader::new(std::io::BufReader::new(file));
+    return match(reader.nth(index.max(1) - 1)) {
+        Some(Ok(game)) => Ok(game),
+        Some(Err(e)) => Err(e.to_string()),
+        None => Err(format!("{} holds fewer than {} games", path, index)),
+    };
+}
+
+// Stops a running search and waits for it to print its bestmove
+fn stop_search(signals: &Arc<Signals>, search_thread: &mut Option<std::thread::JoinHandle<()>>) {
+    if let Some(handle) = search_thread.take() {
+        signals.stop.store(true, Ordering::SeqCst);
+        handle.join().expect("search thread panicked");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(position: &mut Position, command: &str) -> Result<(), PositionError> {
+        let tokens: Vec<String> = command.split_whitespace().map(|token| token.to_string()).collect::<Vec<String>>();
+        return apply_position_command(position, &tokens);
+    }
+
+    #[test]
+    fn applies_position_commands() {
+        let mut position: Position = Position::new();
+        apply(&mut position, "startpos moves e2e4 e7e5 g1f3").unwrap();
+        let (fen, key): (String, u64) = (position.fen(), position.key);
+        apply(&mut position, "startpos moves e2e4 e7e5 g1f3 ! ! > >").unwrap();
+        assert_eq!((position.fen(), position.key), (fen, key));
+        apply(&mut position, "startpos moves e2e4 e7e5 g1f3 ! b1c3").unwrap();
+        assert!(position.redo_stack.is_empty());
+        assert!(matches!(apply(&mut position, "startpos moves e2e4 >"), Err(PositionError::NoMoveToRedo)));
+        assert!(matches!(apply(&mut position, "startpos moves !"), Err(PositionError::NoMoveToUndo)));
+
+        apply(&mut position, "fen 4k3/8/8/8/8/8/4P3/4K3 w - - 0 1 moves e2e3").unwrap();
+        assert_eq!(position.fen(), "4k3/8/8/8/8/4P3/8/4K3 b - - 0 1");
+        // A bad FEN leaves the position untouched
+        assert!(matches!(apply(&mut position, "fen 4k3/8/8 w - - 0 1"), Err(PositionError::InvalidFen { .. })));
+        assert_eq!(position.fen(), "4k3/8/8/8/8/4P3/8/4K3 b - - 0 1");
+        assert!(matches!(apply(&mut position, "somewhere moves e2e4"), Err(PositionError::UnknownPositionSource(_))));
+    }
+}
@@ -0,0 +1,229 @@
+pub mod reader;
+
+use super::position::{error::PositionError, fen::START_FEN, moves::Move, result::GameResult, Position};
+
+const LINE_WIDTH: usize = 80;
+
+// Tags every exported game starts with, in this order
+const SEVEN_TAG_ROSTER: [&str; 7] = ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+#[derive(Debug)]
+pub enum PgnError {
+    Io(std::io::Error),
+    Syntax { line: usize, detail: String },
+    Position { line: usize, error: PositionError }, // A bad FEN tag or a move that can't be played
+}
+
+impl std::fmt::Display for PgnError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match(self) {
+            PgnError::Io(error) => write!(f, "Unable to read PGN: {}", error),
+            PgnError::Syntax { line, detail } => write!(f, "PGN syntax error on line {}: {}", line, detail),
+            PgnError::Position { line, error } => write!(f, "PGN error on line {}: {}", line, error),
+        }
+    }
+}
+
+impl std::error::Error for PgnError {}
+
+impl From<std::io::Error> for PgnError {
+    fn from(error: std::io::Error) -> PgnError {
+        return PgnError::Io(error);
+    }
+}
+
+// One move of the game tree. Its variations are alternatives to this move, played from the same position.
+#[derive(Debug, Clone)]
+pub struct Node {
+    pub chess_move: Move,
+    pub nags: Vec<u8>, // Numeric annotation glyphs, $1 = !, $2 = ?, ...
+    pub comments_before: Vec<String>,
+    pub comments: Vec<String>,
+    pub variations: Vec<Vec<Node>>,
+}
+
+impl Node {
+    pub fn new(chess_move: Move) -> Node {
+        return Node {
+            chess_move,
+            nags: Vec::new(),
+            comments_before: Vec::new(),
+            comments: Vec::new(),
+            variations: Vec::new(),
+        };
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Game {
+    pub tags: Vec<(String, String)>, // In file order
+    pub start: Position, // From the FEN tag, the standard starting position otherwise
+    pub moves: Vec<Node>, // Main line
+    pub comments: Vec<String>, // Comments of a game without moves, there's no move to attach them to
+    pub result: String, // Game termination marker: 1-0, 0-1, 1/2-1/2 or *
+}
+
+impl Game {
+    pub fn new() -> Game {
+        return Game {
+            tags: SEVEN_TAG_ROSTER.iter().map(|&name| (name.to_string(), String::from(if(name == "Result") { "*" } else { "?" }))).collect(),
+            start: Position::new(),
+            moves: Vec::new(),
+            comments: Vec::new(),
+            result: String::from("*"),
+        };
+    }
+
+    // The moves played on position so far become the main line
+    pub fn from_position(position: &Position) -> Game {
+        let mut game: Game = Game::new();
+        let mut start: Position = position.clone();
+        while(start.unmake_move().is_ok()) {}
+        start.redo_stack.clear();
+        if(start.fen() != START_FEN) {
+            game.set_tag("SetUp", "1");
+            game.set_tag("FEN", &start.fen());
+        }
+        game.start = start;
+        game.moves = position.history.iter().map(|record| Node::new(record.chess_move)).collect();
+
+        let result: GameResult = position.game_result();
+        game.result = match(result) {
+            GameResult::Ongoing | GameResult::ThreefoldRepetition | GameResult::FiftyMoveRule => String::from("*"),
+            _ => result.score(position.state.color).to_string(),
+        };
+        let result_tag: String = game.result.clone();
+        game.set_tag("Result", &result_tag);
+        return game;
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        return self.tags.iter().find(|(tag_name, _)| tag_name == name).map(|(_, value)| value.as_str());
+    }
+
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match(self.tags.iter_mut().find(|(tag_name, _)| tag_name == name)) {
+            Some(tag) => tag.1 = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    // Position after the last move of the main line, with the whole line in its history
+    pub fn end_position(&self) -> Position {
+        let mut position: Position = self.start.clone();
+        for node in &self.moves {
+            position.make_move(node.chess_move).expect("game tree holds an illegal move");
+        }
+        return position;
+    }
+}
+
+impl Default for Game {
+    fn default() -> Game {
+        return Game::new();
+    }
+}
+
+// Export format: the seven tag roster first, then the other tags, then movetext wrapped at 80 columns
+impl std::fmt::Display for Game {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut pgn: String = String::new();
+        for &name in SEVEN_TAG_ROSTER.iter() {
+            pgn += &format_tag(name, self.tag(name).unwrap_or(if(name == "Result") { &self.result } else { "?" }));
+        }
+        for (name, value) in self.tags.iter().filter(|(name, _)| !SEVEN_TAG_ROSTER.contains(&name.as_str())) {
+            pgn += &format_tag(name, value);
+        }
+        pgn += "\n";
+
+        let mut tokens: Vec<String> = Vec::new();
+        for comment in &self.comments {
+            push_comment(&mut tokens, comment);
+        }
+        let mut position: Position = self.start.clone();
+        write_line(&mut position, &self.moves, &mut tokens);
+        tokens.push(self.result.clone());
+
+        let mut line: String = String::new();
+        for token in tokens {
+            if(!line.is_empty() && line.len() + 1 + token.len() > LINE_WIDTH) {
+                pgn += &line;
+                pgn += "\n";
+                line.clear();
+            }
+            if(!line.is_empty()) {
+                line += " ";
+            }
+            line += &token;
+        }
+        pgn += &line;
+        pgn += "\n\n";
+        return write!(f, "{}", pgn);
+    }
+}
+
+fn format_tag(name: &str, value: &str) -> String {
+    return format!("[{} \"{}\"]\n", name, value.replace('\\', "\\\\").replace('"', "\\\""));
+}
+
+// Comments are split into words so that line wrapping can break inside them. A '}' would end the
+// comment early and can't be escaped, so it's dropped.
+fn push_comment(tokens: &mut Vec<String>, comment: &str) {
+    let comment: String = comment.replace('}', "");
+    let words: Vec<&str> = comment.split_whitespace().collect::<Vec<&str>>();
+    if(words.is_empty()) {
+        tokens.push(String::from("{}"));
+        return;
+    }
+    for (index, word) in words.iter().enumerate() {
+        let mut token: String = word.to_string();
+        if(index == 0) {
+            token.insert(0, '{');
+        }
+        if(index == words.len() - 1) {
+            token.push('}');
+        }
+        tokens.push(token);
+    }
+}
+
+// Leaves position as it found it
+fn write_line(position: &mut Position, nodes: &[Node], tokens: &mut Vec<String>) {
+    let mut needs_number: bool = true; // Black moves are numbered after a comment or a variation, as in 3... Nf6
+    for node in nodes {
+        for comment in &node.comments_before {
+            push_comment(tokens, comment);
+            needs_number = true;
+        }
+        if(!position.state.color) {
+            tokens.push(format!("{}.", position.state.fullmove_clock));
+        } else if(needs_number) {
+            tokens.push(format!("{}...", position.state.fullmove_clock));
+        }
+        needs_number = false;
+        tokens.push(node.chess_move.to_san(position));
+        for nag in &node.nags {
+            tokens.push(format!("${}", nag));
+        }
+        for comment in &node.comments {
+            push_comment(tokens, comment);
+            needs_number = true;
+        }
+        for variation in &node.variations {
+            let mut variation_tokens: Vec<String> = Vec::new();
+            write_line(position, variation, &mut variation_tokens);
+            if let Some(first) = variation_tokens.first_mut() {
+                first.insert(0, '(');
+            }
+            if let Some(last) = variation_tokens.last_mut() {
+                last.push(')');
+            }
+            tokens.append(&mut variation_tokens);
+            needs_number = true;
+        }
+        position.make_move(node.chess_move).expect("game tree holds an illegal move");
+    }
+    for _ in nodes {
+        position.unmake_move().expect("move was just played");
+    }
+}
@@ -0,0 +1,463 @@
+use std::io::BufRead;
+
+use super::{Game, Node, PgnError};
+use super::super::position::{moves::Move, Position};
+
+// Reads games one at a time, only the text of the game being parsed is held in memory
+pub struct PgnReader<R: BufRead> {
+    reader: R,
+    line_number: usize,
+    pending_line: Option<(String, usize)>, // First tag line, and its number, of the next game, read while looking for the end of the previous one
+}
+
+impl<R: BufRead> PgnReader<R> {
+    pub fn new(reader: R) -> PgnReader<R> {
+        return PgnReader {
+            reader,
+            line_number: 0,
+            pending_line: None,
+        };
+    }
+
+    // A game runs until its termination marker, or failing that until the first tag line that follows its
+    // movetext or the end of the file
+    fn read_game_text(&mut self) -> Result<Option<(String, usize)>, PgnError> {
+        let mut text: String = String::new();
+        let mut first_line: usize = self.line_number + 1;
+        let mut seen_movetext: bool = false;
+        let mut in_comment: bool = false;
+        if let Some((line, line_number)) = self.pending_line.take() {
+            text += &line;
+            first_line = line_number;
+        }
+        let mut line: String = String::new();
+        loop {
+            line.clear();
+            if(self.reader.read_line(&mut line)? == 0) {
+                break;
+            }
+            self.line_number += 1;
+            if(!line.ends_with('\n')) {
+                line.push('\n');
+            }
+            let trimmed: &str = line.trim();
+            if(text.is_empty() && trimmed.is_empty()) {
+                first_line = self.line_number + 1;
+                continue;
+            }
+            if(line.starts_with('%')) {
+                // Escape mechanism, the rest of the line is ignored
+                text += "\n";
+                continue;
+            }
+            if(!in_comment && trimmed.starts_with('[')) {
+                if(seen_movetext) {
+                    self.pending_line = Some((line.clone(), self.line_number));
+                    break;
+                }
+                text += &line;
+                continue;
+            }
+            // Movetext outside comments, with the '*' marker split off from whatever it touches
+            let mut movetext: String = String::new();
+            for character in trimmed.chars() {
+                match(character) {
+                    '{' if !in_comment => in_comment = true,
+                    '}' if in_comment => in_comment = false,
+                    ';' if !in_comment => break,
+                    '*' if !in_comment => movetext += " * ",
+                    _ if !in_comment => movetext.push(character),
+                    _ => (),
+                }
+            }
+            seen_movetext |= !trimmed.is_empty();
+            text += &line;
+            if(movetext.split_whitespace().any(|symbol| ["1-0", "0-1", "1/2-1/2", "*"].contains(&symbol))) {
+                break;
+            }
+        }
+        if(text.is_empty()) {
+            return Ok(None);
+        }
+        return Ok(Some((text, first_line)));
+    }
+}
+
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = Result<Game, PgnError>;
+
+    fn next(&mut self) -> Option<Result<Game, PgnError>> {
+        return match(self.read_game_text()) {
+            Ok(Some((text, first_line))) => Some(parse_game(&text, first_line)),
+            Ok(None) => None,
+            Err(e) => Some(Err(e)),
+        };
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Tag(String, String),
+    Comment(String),
+    Nag(u8),
+    OpenVariation,
+    CloseVariation,
+    Result(String),
+    San(String),
+}
+
+fn syntax_error(line: usize, detail: String) -> PgnError {
+    return PgnError::Syntax {
+        line,
+        detail,
+    };
+}
+
+// Splits a game into tokens, each paired with the line it starts on
+fn tokenize(text: &str, first_line: usize) -> Result<Vec<(Token, usize)>, PgnError> {
+    let characters: Vec<char> = text.chars().collect::<Vec<char>>();
+    let mut tokens: Vec<(Token, usize)> = Vec::new();
+    let mut line: usize = first_line;
+    let mut index: usize = 0;
+    while(index < characters.len()) {
+        let character: char = characters[index];
+        let token_line: usize = line;
+        match(character) {
+            '\n' => {
+                line += 1;
+                index += 1;
+            }
+            _ if character.is_whitespace() => index += 1,
+            '[' => {
+                let end: usize = match(characters[index..].iter().position(|&c| c == '\n')) {
+                    Some(v) => index + v,
+                    None => characters.len(),
+                };
+                let tag_text: String = characters[index..end].iter().collect::<String>();
+                tokens.push((parse_tag(tag_text.trim(), line)?, token_line));
+                index = end;
+            }
+            '{' => {
+                let length: usize = match(characters[index..].iter().position(|&c| c == '}')) {
+                    Some(v) => v,
+                    None => return Err(syntax_error(line, String::from("comment is never closed"))),
+                };
+                let comment: String = characters[index + 1..index + length].iter().collect::<String>();
+                line += comment.matches('\n').count();
+                tokens.push((Token::Comment(comment.split_whitespace().collect::<Vec<&str>>().join(" ")), token_line));
+                index += length + 1;
+            }
+            ';' => {
+                let end: usize = match(characters[index..].iter().position(|&c| c == '\n')) {
+                    Some(v) => index + v,
+                    None => characters.len(),
+                };
+                let comment: String = characters[index + 1..end].iter().collect::<String>();
+                tokens.push((Token::Comment(comment.trim().to_string()), token_line));
+                index = end;
+            }
+            '(' => {
+                tokens.push((Token::OpenVariation, token_line));
+                index += 1;
+            }
+            ')' => {
+                tokens.push((Token::CloseVariation, token_line));
+                index += 1;
+            }
+            '*' => {
+                tokens.push((Token::Result(String::from("*")), token_line));
+                index += 1;
+            }
+            '$' => {
+                let digits: String = characters[index + 1..].iter().take_while(|c| c.is_ascii_digit()).collect::<String>();
+                let nag: u8 = match(digits.parse::<u8>()) {
+                    Ok(v) => v,
+                    Err(_) => return Err(syntax_error(line, format!("'${}' isn't a NAG between $0 and $255", digits))),
+                };
+                tokens.push((Token::Nag(nag), token_line));
+                index += 1 + digits.len();
+            }
+            _ if character.is_ascii_alphanumeric() || character == '.' => {
+                let symbol: String = characters[index..]
+                    .iter()
+                    .take_while(|c| c.is_ascii_alphanumeric() || "_+#=:-/.!?".contains(**c))
+                    .collect::<String>();
+                index += symbol.chars().count();
+                push_symbol(&mut tokens, &symbol, token_line);
+            }
+            _ => return Err(syntax_error(line, format!("unexpected character '{}'", character))),
+        }
+    }
+    return Ok(tokens);
+}
+
+// [Name "value"] with \" and \\ escapes inside the value
+fn parse_tag(tag_text: &str, line: usize) -> Result<Token, PgnError> {
+    let malformed = || syntax_error(line, format!("malformed tag pair '{}'", tag_text));
+    let inner: &str = tag_text.strip_prefix('[').and_then(|s| s.strip_suffix(']')).ok_or_else(malformed)?.trim();
+    let (name, quoted): (&str, &str) = inner.split_once(char::is_whitespace).ok_or_else(malformed)?;
+    let quoted: &str = quoted.trim();
+    if(name.is_empty() || quoted.len() < 2 || !quoted.starts_with('"') || !quoted.ends_with('"')) {
+        return Err(malformed());
+    }
+    let mut value: String = String::new();
+    let mut escaped: bool = false;
+    for character in quoted[1..quoted.len() - 1].chars() {
+        match(character) {
+            '\\' if !escaped => escaped = true,
+            _ => {
+                value.push(character);
+                escaped = false;
+            }
+        }
+    }
+    return Ok(Token::Tag(name.to_string(), value));
+}
+
+// Move numbers are dropped, results recognized, and !/? suffixes turned into NAGs
+fn push_symbol(tokens: &mut Vec<(Token, usize)>, symbol: &str, line: usize) {
+    if(["1-0", "0-1", "1/2-1/2"].contains(&symbol)) {
+        tokens.push((Token::Result(symbol.to_string()), line));
+        return;
+    }
+    let digits: usize = symbol.chars().take_while(|c| c.is_ascii_digit()).count();
+    let mut san: &str = symbol;
+    if(symbol[digits..].starts_with('.') || digits == symbol.len()) {
+        // A move number, with or without its dots
+        san = symbol[digits..].trim_start_matches('.');
+    }
+    if(san.is_empty()) {
+        return;
+    }
+    let annotation_start: usize = san.find(['!', '?']).unwrap_or(san.len());
+    let nag: Option<u8> = match(&san[annotation_start..]) {
+        "!" => Some(1),
+        "?" => Some(2),
+        "!!" => Some(3),
+        "??" => Some(4),
+        "!?" => Some(5),
+        "?!" => Some(6),
+        _ => None,
+    };
+    tokens.push((Token::San(san[..annotation_start].to_string()), line));
+    if let Some(v) = nag {
+        tokens.push((Token::Nag(v), line));
+    }
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    index: usize,
+    result: Option<String>,
+    comments: Vec<String>, // Left over from a main line without moves
+}
+
+impl Parser {
+    fn line(&self) -> usize {
+        return match(self.tokens.get(self.index).or(self.tokens.last())) {
+            Some((_, line)) => *line,
+            None => 0,
+        };
+    }
+
+    // Parses moves until the end of the variation and leaves position as it found it
+    fn parse_line(&mut self, position: &mut Position, depth: usize) -> Result<Vec<Node>, PgnError> {
+        let mut nodes: Vec<Node> = Vec::new();
+        let mut pending_comments: Vec<String> = Vec::new();
+        loop {
+            let line: usize = self.line();
+            let token: Token = match(self.tokens.get(self.index)) {
+                Some((token, _)) => token.clone(),
+                None if depth == 0 => break,
+                None => return Err(syntax_error(line, String::from("variation is never closed"))),
+            };
+            self.index += 1;
+            match(token) {
+                Token::San(san) => {
+                    let chess_move: Move = Move::from_san(&san, position).map_err(|error| PgnError::Position { line, error })?;
+                    let mut node: Node = Node::new(chess_move);
+                    node.comments_before.append(&mut pending_comments);
+                    position.make_move(chess_move).map_err(|error| PgnError::Position { line, error })?;
+                    nodes.push(node);
+                }
+                Token::Nag(nag) => match(nodes.last_mut()) {
+                    Some(node) => node.nags.push(nag),
+                    None => return Err(syntax_error(line, format!("${} doesn't follow a move", nag))),
+                },
+                Token::Comment(comment) => match(nodes.last_mut()) {
+                    Some(node) => node.comments.push(comment),
+                    None => pending_comments.push(comment),
+                },
+                Token::OpenVariation => {
+                    if(nodes.is_empty()) {
+                        return Err(syntax_error(line, String::from("variation doesn't follow a move")));
+                    }
+                    position.unmake_move().expect("move was just played");
+                    let variation: Vec<Node> = self.parse_line(position, depth + 1)?;
+                    let last: &mut Node = nodes.last_mut().unwrap();
+                    position.make_move(last.chess_move).expect("move was played before");
+                    last.variations.push(variation);
+                }
+                Token::CloseVariation if depth > 0 => break,
+                Token::CloseVariation => return Err(syntax_error(line, String::from("')' closes no variation"))),
+                Token::Result(result) if depth == 0 => {
+                    self.result = Some(result);
+                    break;
+                }
+                Token::Result(result) => return Err(syntax_error(line, format!("result {} inside a variation", result))),
+                Token::Tag(name, _) => return Err(syntax_error(line, format!("tag pair {} inside the movetext", name))),
+            }
+        }
+        match(nodes.last_mut()) {
+            Some(node) => node.comments.append(&mut pending_comments),
+            None if depth == 0 => self.comments.append(&mut pending_comments),
+            None => (),
+        }
+        for _ in &nodes {
+            position.unmake_move().expect("move was just played");
+        }
+        return Ok(nodes);
+    }
+}
+
+pub fn parse_game(text: &str, first_line: usize) -> Result<Game, PgnError> {
+    let mut tokens: Vec<(Token, usize)> = tokenize(text, first_line)?;
+    let movetext_start: usize = tokens.iter().position(|(token, _)| !matches!(token, Token::Tag(_, _))).unwrap_or(tokens.len());
+    let tags: Vec<(String, String)> = tokens.drain(..movetext_start)
+        .map(|(token, _)| match(token) {
+            Token::Tag(name, value) => (name, value),
+            _ => unreachable!(),
+        })
+        .collect::<Vec<(String, String)>>();
+
+    let mut game: Game = Game::new();
+    game.tags = tags;
+    if let Some(fen) = game.tag("FEN") {
+        game.start = Position::from_fen(fen).map_err(|error| PgnError::Position { line: first_line, error })?;
+    }
+
+    let mut parser: Parser = Parser {
+        tokens,
+        index: 0,
+        result: None,
+        comments: Vec::new(),
+    };
+    let mut position: Position = game.start.clone();
+    game.moves = parser.parse_line(&mut position, 0)?;
+    if(parser.index < parser.tokens.len()) {
+        return Err(syntax_error(parser.line(), String::from("movetext continues after the game result")));
+    }
+    game.comments = parser.comments;
+    game.result = match(parser.result) {
+        Some(v) => v,
+        None => game.tag("Result").unwrap_or("*").to_string(),
+    };
+    return Ok(game);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GAMES: &str = r#"[Event "Casual \"blitz\""]
+[Site "?"]
+[Date "2024.01.01"]
+[Round "-"]
+[White "Alice"]
+[Black "Bob"]
+[Result "1-0"]
+
+1. e4 {Best by test} e5 2. Nf3 (2. f4 exf4 (2... d5) 3. Nf3) 2... Nc6 $1 3. Bb5 a6?!
+; a rest-of-line comment
+4. Ba4 Nf6 5. O-O 1-0
+
+[Event "Second"]
+[SetUp "1"]
+[FEN "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1"]
+[Result "*"]
+
+1. e4 Kd7 *
+"#;
+
+    #[test]
+    fn reads_games() {
+        let games: Vec<Game> = PgnReader::new(GAMES.as_bytes()).collect::<Result<Vec<Game>, PgnError>>().unwrap();
+        assert_eq!(games.len(), 2);
+
+        let first: &Game = &games[0];
+        assert_eq!(first.tag("Event"), Some("Casual \"blitz\""));
+        assert_eq!(first.result, "1-0");
+        assert_eq!(first.moves.len(), 9);
+        assert_eq!(first.moves[0].comments, vec![String::from("Best by test")]);
+        assert_eq!(first.moves[2].variations.len(), 1);
+        assert_eq!(first.moves[2].variations[0][1].variations[0][0].chess_move.to_string(), "d7d5");
+        assert_eq!(first.moves[3].nags, vec![1]);
+        assert_eq!(first.moves[5].nags, vec![6]);
+        assert_eq!(first.moves[5].comments, vec![String::from("a rest-of-line comment")]);
+        assert_eq!(first.end_position().fen(), "r1bqkb1r/1ppp1ppp/p1n2n2/4p3/B3P3/5N2/PPPP1PPP/RNBQ1RK1 b kq - 3 5");
+
+        let second: &Game = &games[1];
+        assert_eq!(second.result, "*");
+        assert_eq!(second.end_position().fen(), "8/3k4/8/8/4P3/8/8/4K3 w - - 1 2");
+    }
+
+    #[test]
+    fn writes_games_back() {
+        let games: Vec<Game> = PgnReader::new(GAMES.as_bytes()).collect::<Result<Vec<Game>, PgnError>>().unwrap();
+        let written: String = games.iter().map(|game| game.to_string()).collect::<String>();
+        assert!(written.lines().all(|line| line.len() <= 80));
+        assert!(written.contains("1. e4 {Best by test} 1... e5 2. Nf3 (2. f4 exf4 (2... d5) 3. Nf3) 2... Nc6 $1"));
+
+        let reread: Vec<Game> = PgnReader::new(written.as_bytes()).collect::<Result<Vec<Game>, PgnError>>().unwrap();
+        assert_eq!(reread.len(), 2);
+        assert_eq!(reread[0].to_string(), games[0].to_string());
+        assert_eq!(reread[1].to_string(), games[1].to_string());
+    }
+
+    #[test]
+    fn splits_games_on_results() {
+        let text: &str = "1. e4 e5 2. Nf3 1-0\n\n1. d4 {no tags, ends with *} d5*\n\n1 c4 e5 2 Nc3 1/2-1/2\n";
+        let games: Vec<Game> = PgnReader::new(text.as_bytes()).collect::<Result<Vec<Game>, PgnError>>().unwrap();
+        assert_eq!(games.len(), 3);
+        assert_eq!((games[0].moves.len(), games[0].result.as_str()), (3, "1-0"));
+        assert_eq!((games[1].moves.len(), games[1].result.as_str()), (2, "*"));
+        // Move numbers without their dots
+        assert_eq!((games[2].moves.len(), games[2].result.as_str()), (3, "1/2-1/2"));
+        assert_eq!(games[2].moves[2].chess_move.to_string(), "b1c3");
+
+        // A result inside a comment doesn't end the game
+        let commented: Vec<Game> = PgnReader::new("1. e4 {1-0 was agreed\nlater} e5 *\n".as_bytes()).collect::<Result<Vec<Game>, PgnError>>().unwrap();
+        assert_eq!(commented.len(), 1);
+        assert_eq!(commented[0].moves.len(), 2);
+    }
+
+    #[test]
+    fn keeps_comments_without_moves() {
+        let game: Game = PgnReader::new("[Event \"Adjourned\"]\n\n{Never started} {Rescheduled} *\n".as_bytes()).next().unwrap().unwrap();
+        assert!(game.moves.is_empty());
+        assert_eq!(game.comments, vec![String::from("Never started"), String::from("Rescheduled")]);
+        assert!(game.to_string().ends_with("\n{Never started} {Rescheduled} *\n\n"));
+        let reread: Game = PgnReader::new(game.to_string().as_bytes()).next().unwrap().unwrap();
+        assert_eq!(reread.comments, game.comments);
+    }
+
+    #[test]
+    fn strips_braces_from_comments() {
+        let mut game: Game = Game::new();
+        game.start = Position::from("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1");
+        let mut node: Node = Node::new(Move::from_uci("e2e4", &game.start).unwrap());
+        node.comments.push(String::from("see {below}"));
+        game.moves.push(node);
+        assert!(game.to_string().contains("1. e4 {see {below} *"));
+        let reread: Game = PgnReader::new(game.to_string().as_bytes()).next().unwrap().unwrap();
+        assert_eq!(reread.moves[0].comments, vec![String::from("see {below")]);
+    }
+
+    #[test]
+    fn reports_errors() {
+        let mut illegal = PgnReader::new("1. e4 e5 2. Ke3 *\n".as_bytes());
+        assert!(matches!(illegal.next(), Some(Err(PgnError::Position { line: 1, .. }))));
+        let mut unclosed = PgnReader::new("[Event \"?\"]\n\n1. e4 (1. d4 *\n".as_bytes());
+        assert!(matches!(unclosed.next(), Some(Err(PgnError::Syntax { .. }))));
+    }
+}
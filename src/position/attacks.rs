@@ -0,0 +1,63 @@
+use super::bitboard::{self, Bitboard, Squares};
+use super::Position;
+
+impl Position {
+    pub fn is_square_attacked(&self, square: u8, by_color: bool) -> bool {
+        return self.bitboards.is_square_attacked(square, by_color);
+    }
+
+    // Pieces of both colors attacking the square
+    pub fn attackers_to(&self, square: u8) -> Bitboard {
+        let occupied: Bitboard = self.bitboards.occupied();
+        return self.bitboards.attackers(square, false, occupied) | self.bitboards.attackers(square, true, occupied);
+    }
+
+    // Enemy pieces giving check to the side to move
+    pub fn checkers(&self) -> Bitboard {
+        let king_square: u8 = self.bitboards.king_square(self.state.color);
+        return self.bitboards.attackers(king_square, !self.state.color, self.bitboards.occupied());
+    }
+
+    pub fn in_check(&self) -> bool {
+        return self.checkers() != 0;
+    }
+
+    // Pieces of the side to move that can't leave the line between their king and an enemy slider
+    pub fn pinned_pieces(&self) -> Bitboard {
+        let color: bool = self.state.color;
+        let king_square: u8 = self.bitboards.king_square(color);
+        let enemies: Bitboard = self.bitboards.colors[!color as usize];
+        let queens: Bitboard = self.bitboards.pieces[0b101];
+
+        // Enemy sliders that would attack the king if only enemy pieces stood in the way
+        let snipers: Bitboard = (bitboard::rook_attacks(king_square, enemies) & (self.bitboards.pieces[0b100] | queens) & enemies)
+            | (bitboard::bishop_attacks(king_square, enemies) & (self.bitboards.pieces[0b011] | queens) & enemies);
+        let own: Bitboard = self.bitboards.colors[color as usize];
+        let mut pinned: Bitboard = 0;
+        for sniper in Squares(snipers) {
+            let blockers: Bitboard = bitboard::between(king_square, sniper) & self.bitboards.occupied();
+            if(bitboard::popcount(blockers) == 1 && blockers & own != 0) {
+                pinned |= blockers;
+            }
+        }
+        return pinned;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_checkers_and_pins() {
+        let double_check: Position = Position::from("7k/8/8/8/4r3/3n4/8/4K3 w - - 0 1");
+        assert_eq!(double_check.checkers(), (1 << 19) | (1 << 28));
+        assert!(double_check.in_check());
+
+        // The e2 knight is pinned by the e8 rook, the c3 bishop isn't since two pieces stand before the a5 queen
+        let pins: Position = Position::from("k3r3/8/8/q7/1P6/2B5/4N3/4K3 w - - 0 1");
+        assert_eq!(pins.pinned_pieces(), 1 << 12);
+        assert!(!pins.in_check());
+        assert_eq!(pins.attackers_to(27), (1 << 18) | (1 << 12));
+    }
+}
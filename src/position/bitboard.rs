@@ -0,0 +1,223 @@
+use super::{magic, piece::Piece};
+
+// One bit per square, bit 0 = a1 .. bit 63 = h8 like the mailbox indices
+pub type Bitboard = u64;
+
+pub const FILE_A: Bitboard = 0x0101_0101_0101_0101;
+pub const FILE_H: Bitboard = FILE_A << 7;
+pub const RANK_1: Bitboard = 0xFF;
+pub const RANK_3: Bitboard = RANK_1 << 16;
+pub const RANK_6: Bitboard = RANK_1 << 40;
+pub const RANK_8: Bitboard = RANK_1 << 56;
+
+// Piece placement split by piece type (indexed like Piece.data, index 0 unused) and by color (0 = WHITE, 1 = BLACK)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bitboards {
+    pub pieces: [Bitboard; 7],
+    pub colors: [Bitboard; 2],
+}
+
+impl Bitboards {
+    pub fn from_board(board: &[Piece; 64]) -> Bitboards {
+        let mut bitboards: Bitboards = Bitboards {
+            pieces: [0; 7],
+            colors: [0; 2],
+        };
+        for (square, piece) in board.iter().enumerate() {
+            if(piece.data & 0b111 != 0) {
+                bitboards.toggle(square as u8, piece.data);
+            }
+        }
+        return bitboards;
+    }
+
+    // Adds the piece when the square is empty, removes it when it's there
+    pub fn toggle(&mut self, square: u8, data: u8) {
+        if(data & 0b111 == 0) {
+            return;
+        }
+        let bit: Bitboard = 1 << square;
+        self.pieces[(data & 0b111) as usize] ^= bit;
+        self.colors[((data >> 3) & 1) as usize] ^= bit;
+    }
+
+    pub fn occupied(&self) -> Bitboard {
+        return self.colors[0] | self.colors[1];
+    }
+
+    // Pieces of one type and color, color being false = WHITE, true = BLACK
+    pub fn of(&self, piece_type: u8, color: bool) -> Bitboard {
+        return self.pieces[piece_type as usize] & self.colors[color as usize];
+    }
+
+    pub fn king_square(&self, color: bool) -> u8 {
+        return lsb(self.of(0b110, color));
+    }
+
+    // Every piece of by_color (false = WHITE, true = BLACK) attacking the square, given an occupancy for the sliders
+    pub fn attackers(&self, square: u8, by_color: bool, occupied: Bitboard) -> Bitboard {
+        let rooks_and_queens: Bitboard = self.pieces[0b100] | self.pieces[0b101];
+        let bishops_and_queens: Bitboard = self.pieces[0b011] | self.pieces[0b101];
+        let attackers: Bitboard = (PAWN_ATTACKS[!by_color as usize][square as usize] & self.pieces[0b001])
+            | (KNIGHT_ATTACKS[square as usize] & self.pieces[0b010])
+            | (KING_ATTACKS[square as usize] & self.pieces[0b110])
+            | (rook_attacks(square, occupied) & rooks_and_queens)
+            | (bishop_attacks(square, occupied) & bishops_and_queens);
+        return attackers & self.colors[by_color as usize] & occupied;
+    }
+
+    pub fn is_square_attacked(&self, square: u8, by_color: bool) -> bool {
+        return self.attackers(square, by_color, self.occupied()) != 0;
+    }
+}
+
+pub fn popcount(bitboard: Bitboard) -> u32 {
+    return bitboard.count_ones();
+}
+
+pub fn lsb(bitboard: Bitboard) -> u8 {
+    return bitboard.trailing_zeros() as u8;
+}
+
+// Removes the lowest set square and returns it
+pub fn pop_lsb(bitboard: &mut Bitboard) -> u8 {
+    let square: u8 = lsb(*bitboard);
+    *bitboard &= *bitboard - 1;
+    return square;
+}
+
+// Iterates over the set squares, lowest first
+pub struct Squares(pub Bitboard);
+
+impl Iterator for Squares {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        if(self.0 == 0) {
+            return None;
+        }
+        return Some(pop_lsb(&mut self.0));
+    }
+}
+
+// Squares reached from square by the (row, col) steps, one step each
+const fn step_attacks(square: usize, steps: &[(i32, i32)]) -> Bitboard {
+    let row: i32 = (square / 8) as i32;
+    let col: i32 = (square % 8) as i32;
+    let mut attacks: Bitboard = 0;
+    let mut i: usize = 0;
+    while(i < steps.len()) {
+        let target_row: i32 = row + steps[i].0;
+        let target_col: i32 = col + steps[i].1;
+        if(target_row >= 0 && target_row < 8 && target_col >= 0 && target_col < 8) {
+            attacks |= 1 << (target_row * 8 + target_col);
+        }
+        i += 1;
+    }
+    return attacks;
+}
+
+const KNIGHT_STEPS: [(i32, i32); 8] = [(1, 2), (2, 1), (2, -1), (1, -2), (-1, -2), (-2, -1), (-2, 1), (-1, 2)];
+const KING_STEPS: [(i32, i32); 8] = [(1, 0), (-1, 0), (0, 1), (0, -1), (1, 1), (1, -1), (-1, 1), (-1, -1)];
+// Positive directions first so that the nearest blocker is the lowest bit for them and the highest for the others
+const DIRECTIONS: [(i32, i32); 8] = [(1, 0), (0, 1), (1, 1), (1, -1), (-1, 0), (0, -1), (-1, -1), (-1, 1)];
+
+const fn generate_step_table(steps: &[(i32, i32)]) -> [Bitboard; 64] {
+    let mut table: [Bitboard; 64] = [0; 64];
+    let mut square: usize = 0;
+    while(square < 64) {
+        table[square] = step_attacks(square, steps);
+        square += 1;
+    }
+    return table;
+}
+
+const fn generate_pawn_table() -> [[Bitboard; 64]; 2] {
+    let mut table: [[Bitboard; 64]; 2] = [[0; 64]; 2];
+    let mut square: usize = 0;
+    while(square < 64) {
+        table[0][square] = step_attacks(square, &[(1, -1), (1, 1)]);
+        table[1][square] = step_attacks(square, &[(-1, -1), (-1, 1)]);
+        square += 1;
+    }
+    return table;
+}
+
+// Every square along a direction up to the edge of the board, blockers ignored
+const fn generate_rays() -> [[Bitboard; 64]; 8] {
+    let mut rays: [[Bitboard; 64]; 8] = [[0; 64]; 8];
+    let mut direction: usize = 0;
+    while(direction < 8) {
+        let mut square: usize = 0;
+        while(square < 64) {
+            let mut row: i32 = (square / 8) as i32 + DIRECTIONS[direction].0;
+            let mut col: i32 = (square % 8) as i32 + DIRECTIONS[direction].1;
+            while(row >= 0 && row < 8 && col >= 0 && col < 8) {
+                rays[direction][square] |= 1 << (row * 8 + col);
+                row += DIRECTIONS[direction].0;
+                col += DIRECTIONS[direction].1;
+            }
+            square += 1;
+        }
+        direction += 1;
+    }
+    return rays;
+}
+
+pub static KNIGHT_ATTACKS: [Bitboard; 64] = generate_step_table(&KNIGHT_STEPS);
+pub static KING_ATTACKS: [Bitboard; 64] = generate_step_table(&KING_STEPS);
+// Indexed by the color of the attacking pawn, then its square
+pub static PAWN_ATTACKS: [[Bitboard; 64]; 2] = generate_pawn_table();
+static RAYS: [[Bitboard; 64]; 8] = generate_rays();
+
+// Squares a slider reaches along the given directions, stopping on (and including) the first blocker
+fn ray_attacks(square: u8, occupied: Bitboard, directions: [usize; 4]) -> Bitboard {
+    let mut attacks: Bitboard = 0;
+    for direction in directions {
+        let ray: Bitboard = RAYS[direction][square as usize];
+        let blockers: Bitboard = ray & occupied;
+        if(blockers == 0) {
+            attacks |= ray;
+            continue;
+        }
+        let blocker: usize = match(direction < 4) {
+            true => blockers.trailing_zeros() as usize,
+            false => 63 - blockers.leading_zeros() as usize,
+        };
+        attacks |= ray ^ RAYS[direction][blocker];
+    }
+    return attacks;
+}
+
+// Ray walking versions, only used to fill the magic tables
+pub(super) fn ray_rook_attacks(square: u8, occupied: Bitboard) -> Bitboard {
+    return ray_attacks(square, occupied, [0, 1, 4, 5]);
+}
+
+pub(super) fn ray_bishop_attacks(square: u8, occupied: Bitboard) -> Bitboard {
+    return ray_attacks(square, occupied, [2, 3, 6, 7]);
+}
+
+pub fn rook_attacks(square: u8, occupied: Bitboard) -> Bitboard {
+    return magic::rook_attacks(square, occupied);
+}
+
+pub fn bishop_attacks(square: u8, occupied: Bitboard) -> Bitboard {
+    return magic::bishop_attacks(square, occupied);
+}
+
+// Squares strictly between two squares on a shared rank, file or diagonal, empty when they aren't aligned
+pub fn between(from: u8, to: u8) -> Bitboard {
+    let to_bit: Bitboard = 1 << to;
+    if(rook_attacks(from, 0) & to_bit != 0) {
+        return rook_attacks(from, to_bit) & rook_attacks(to, 1 << from);
+    }
+    if(bishop_attacks(from, 0) & to_bit != 0) {
+        return bishop_attacks(from, to_bit) & bishop_attacks(to, 1 << from);
+    }
+    return 0;
+}
+
+pub fn queen_attacks(square: u8, occupied: Bitboard) -> Bitboard {
+    return rook_attacks(square, occupied) | bishop_attacks(square, occupied);
+}
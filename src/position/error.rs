@@ -0,0 +1,24 @@
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PositionError {
+    IllegalMove { mv: String, reason: String },
+    NoMoveToUndo,
+    NoMoveToRedo,
+    InvalidSquare(String),
+    InvalidFen { field: &'static str, detail: String },
+    UnknownPositionSource(String), // "position" followed by something other than startpos or fen
+}
+
+impl std::fmt::Display for PositionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match(self) {
+            PositionError::IllegalMove { mv, reason } => write!(f, "Illegal move {}: {}", mv, reason),
+            PositionError::NoMoveToUndo => write!(f, "No move to undo"),
+            PositionError::NoMoveToRedo => write!(f, "No move to redo"),
+            PositionError::InvalidSquare(square) => write!(f, "Invalid square '{}'", square),
+            PositionError::InvalidFen { field, detail } => write!(f, "Invalid FEN {}: {}", field, detail),
+            PositionError::UnknownPositionSource(source) => write!(f, "Expected 'startpos' or 'fen <fen>' after position, got '{}'", source),
+        }
+    }
+}
+
+impl std::error::Error for PositionError {}
@@ -1,6 +1,6 @@
 pub const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
 
-use super::piece::Piece;
+use super::{bitboard::Bitboards, error::PositionError, piece::Piece};
 
 #[derive(Clone)]
 pub struct ParsedFEN {
@@ -12,59 +12,200 @@ pub struct ParsedFEN {
     pub fullmove_clock: u16,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FenError {
+    pub field: &'static str, // "fen", "board", "color", "castling", "en passant", "halfmove clock" or "fullmove clock"
+    pub detail: String,
+}
+
+impl std::fmt::Display for FenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid FEN {}: {}", self.field, self.detail)
+    }
+}
+
+impl std::error::Error for FenError {}
+
+impl From<FenError> for PositionError {
+    fn from(error: FenError) -> PositionError {
+        return PositionError::InvalidFen {
+            field: error.field,
+            detail: error.detail,
+        };
+    }
+}
+
+fn fen_error(field: &'static str, detail: String) -> FenError {
+    return FenError {
+        field,
+        detail,
+    };
+}
+
 impl ParsedFEN {
+    // For FEN strings known to be valid, anything else should go through parse
     pub fn from(fen_string: &str) -> ParsedFEN {
-        let mut square_index: usize = 56;
-        let mut char_index: usize = 0;
-        let fen_parts: Vec<&str> = fen_string.split(" ").collect::<Vec<&str>>();
-        let board_chars: Vec<char> = fen_parts[0].chars().collect::<Vec<char>>();
+        return match(ParsedFEN::parse(fen_string)) {
+            Ok(v) => v,
+            Err(e) => panic!("{} in '{}'", e, fen_string),
+        };
+    }
+
+    // The halfmove and fullmove clocks may be left out, they then default to 0 and 1
+    pub fn parse(fen_string: &str) -> Result<ParsedFEN, FenError> {
+        let fen_parts: Vec<&str> = fen_string.split_whitespace().collect::<Vec<&str>>();
+        if(fen_parts.len() < 4 || fen_parts.len() > 6) {
+            return Err(fen_error("fen", format!("expected 4 to 6 fields, found {}", fen_parts.len())));
+        }
+
+        let board: [Piece; 64] = ParsedFEN::parse_board(fen_parts[0])?;
+        let color: bool = match(fen_parts[1]) {
+            "w" => false,
+            "b" => true,
+            other => return Err(fen_error("color", format!("expected 'w' or 'b', found '{}'", other))),
+        };
+        let castle: u8 = ParsedFEN::parse_castle_rights(fen_parts[2], &board)?;
+        let en_passant: u8 = ParsedFEN::parse_en_passant(fen_parts[3], &board, color)?;
+        let halfmove_clock: u8 = match(fen_parts.get(4)) {
+            Some(v) => v.parse::<u8>().map_err(|_| fen_error("halfmove clock", format!("'{}' isn't a number between 0 and 255", v)))?,
+            None => 0,
+        };
+        let fullmove_clock: u16 = match(fen_parts.get(5)) {
+            Some(v) => match(v.parse::<u16>()) {
+                Ok(n) if n >= 1 => n,
+                _ => return Err(fen_error("fullmove clock", format!("'{}' isn't a number of at least 1", v))),
+            },
+            None => 1,
+        };
+
+        // The side that just moved can't have left its king in check
+        let bitboards: Bitboards = Bitboards::from_board(&board);
+        if(bitboards.is_square_attacked(bitboards.king_square(!color), color)) {
+            return Err(fen_error("board", String::from("the side not to move is in check")));
+        }
+
+        return Ok(ParsedFEN {
+            board,
+            color,
+            castle,
+            en_passant,
+            halfmove_clock,
+            fullmove_clock,
+        });
+    }
+
+    fn parse_board(board_text: &str) -> Result<[Piece; 64], FenError> {
         let mut board: [Piece; 64] = [Piece {data:0,pos:0}; 64];
-        while(char_index < board_chars.len()) {
-            let character: &char = &board_chars[char_index];
-            let space_num: u8 = match(character.to_string().parse::<u8>()) {
-                Ok(v) => v,
-                Err(_) => 0, // numbers outside of range 1..=8 are impossible in fen strings
+        let ranks: Vec<&str> = board_text.split('/').collect::<Vec<&str>>();
+        if(ranks.len() != 8) {
+            return Err(fen_error("board", format!("expected 8 ranks, found {}", ranks.len())));
+        }
+        for (rank_index, rank_text) in ranks.iter().enumerate() {
+            let row: usize = 7 - rank_index;
+            let mut col: usize = 0;
+            let mut previous_was_digit: bool = false;
+            for character in rank_text.chars() {
+                if let Some(space_num) = character.to_digit(10) {
+                    if(space_num == 0 || space_num > 8 || previous_was_digit) {
+                        return Err(fen_error("board", format!("bad empty square count in rank {}", row + 1)));
+                    }
+                    col += space_num as usize;
+                    previous_was_digit = true;
+                } else {
+                    if(!"pnbrqkPNBRQK".contains(character)) {
+                        return Err(fen_error("board", format!("'{}' isn't a piece letter", character)));
+                    }
+                    if(col < 8) {
+                        board[row * 8 + col] = Piece::from(&character, &((row * 8 + col) as u8));
+                    }
+                    col += 1;
+                    previous_was_digit = false;
+                }
+                if(col > 8) {
+                    return Err(fen_error("board", format!("rank {} has more than 8 squares", row + 1)));
+                }
+            }
+            if(col != 8) {
+                return Err(fen_error("board", format!("rank {} has {} squares instead of 8", row + 1, col)));
+            }
+        }
+
+        for (color, name) in [(0, "white"), (0b1000, "black")] {
+            let count = |piece_type: u8| -> usize {
+                board.iter().filter(|piece| piece.data & 0b1111 == piece_type | color).count()
             };
-            if(character == &'/') {
-                square_index -= 16;
-                char_index += 1;
-                continue;
+            if(count(0b110) != 1) {
+                return Err(fen_error("board", format!("{} has {} kings instead of 1", name, count(0b110))));
             }
-            if(space_num != 0) {
-                for _ in 0..space_num {
-                    board[square_index] = Piece::from(&' ', &(square_index as u8));
-                    square_index += 1;
-                }
-                char_index += 1;
-                continue;
+            if(count(0b001) > 8) {
+                return Err(fen_error("board", format!("{} has more than 8 pawns", name)));
+            }
+            let piece_count: usize = board.iter().filter(|piece| piece.data & 0b111 != 0 && piece.data & 0b1000 == color).count();
+            if(piece_count > 16) {
+                return Err(fen_error("board", format!("{} has more than 16 pieces", name)));
             }
-            board[square_index] = Piece::from(&character, &(square_index as u8));
-            char_index += 1;
-            square_index += 1;
         }
-        return ParsedFEN {
-            board,
-            color: match(fen_parts[1]) {
-                "w" => false,
-                _ => true,
-            },
-            castle: ParsedFEN::parse_castle_text(fen_parts[2]),
-            en_passant: super::parse_square(fen_parts[3]),
-            halfmove_clock: match(fen_parts[4].parse::<u8>()) {
-                Ok(v) => v,
-                Err(_) => 0,
-            },
-            fullmove_clock: match(fen_parts[5].parse::<u16>()) {
-                Ok(v) => v,
-                Err(_) => 1,
-            },
+        for square in (0..8).chain(56..64) {
+            if(board[square].data & 0b111 == 0b001) {
+                return Err(fen_error("board", format!("pawn on the back rank at {}", super::to_square(&(square as u8)))));
+            }
+        }
+        return Ok(board);
+    }
+
+    // Every right needs its king and rook still on their starting squares
+    fn parse_castle_rights(castle_text: &str, board: &[Piece; 64]) -> Result<u8, FenError> {
+        if(castle_text == "-") {
+            return Ok(0);
+        }
+        let mut castle: u8 = 0;
+        for character in castle_text.chars() {
+            let (right, king_square, rook_square, piece_color): (u8, usize, usize, u8) = match(character) {
+                'K' => (0b1000, 4, 7, 0),
+                'Q' => (0b0100, 4, 0, 0),
+                'k' => (0b0010, 60, 63, 0b1000),
+                'q' => (0b0001, 60, 56, 0b1000),
+                other => return Err(fen_error("castling", format!("'{}' isn't one of KQkq", other))),
+            };
+            if(castle & right != 0) {
+                return Err(fen_error("castling", format!("'{}' appears twice", character)));
+            }
+            if(board[king_square].data & 0b1111 != 0b110 | piece_color || board[rook_square].data & 0b1111 != 0b100 | piece_color) {
+                return Err(fen_error("castling", format!("'{}' needs the king and rook on their starting squares", character)));
+            }
+            castle |= right;
         }
+        return Ok(castle);
     }
 
+    // The en passant square must sit right behind a pawn that could just have moved two squares
+    fn parse_en_passant(en_passant_text: &str, board: &[Piece; 64], color: bool) -> Result<u8, FenError> {
+        if(en_passant_text == "-") {
+            return Ok(64);
+        }
+        let square: u8 = super::moves::try_parse_square(en_passant_text)
+            .map_err(|_| fen_error("en passant", format!("'{}' isn't a square", en_passant_text)))?;
+        let (expected_row, pawn_square, origin_square, pawn_data): (u8, i8, i8, u8) = match(color) {
+            false => (5, square as i8 - 8, square as i8 + 8, 0b1001),
+            true => (2, square as i8 + 8, square as i8 - 8, 0b0001),
+        };
+        if(square >> 3 != expected_row) {
+            return Err(fen_error("en passant", format!("{} isn't on the rank a pawn of the side not to move skips", en_passant_text)));
+        }
+        if(board[pawn_square as usize].data & 0b1111 != pawn_data
+            || board[square as usize].data != 0
+            || board[origin_square as usize].data != 0) {
+            return Err(fen_error("en passant", format!("no pawn can just have skipped {}", en_passant_text)));
+        }
+        return Ok(square);
+    }
+
+    #[allow(clippy::new_without_default)]
     pub fn new() -> ParsedFEN {
         return ParsedFEN::from(START_FEN);
     }
 
+    #[allow(clippy::inherent_to_string, clippy::needless_borrow)]
     pub fn to_string(&self) -> String {
         let mut square_index: usize = 56;
         let mut empty_space_counter: u8 = 0;
@@ -93,6 +234,10 @@ impl ParsedFEN {
             }
             square_index += 1;
         }
+        if(empty_space_counter != 0) {
+            // Empty squares ending on h1 aren't followed by a '/' so they are flushed here
+            fen_string += &empty_space_counter.to_string();
+        }
         let char_color: char = match((&self).color) {
             true => 'b',
             false => 'w',
@@ -123,6 +268,7 @@ impl ParsedFEN {
         return total;
     }
 
+    #[allow(clippy::op_ref)]
     pub fn parse_castle_value(castle_value: &u8) -> String {
         if(castle_value == &0) {
             return String::from("-");
@@ -160,4 +306,40 @@ impl std::fmt::Debug for ParsedFEN {
             self.fullmove_clock,
         )
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error_field(fen: &str) -> &'static str {
+        return ParsedFEN::parse(fen).expect_err(fen).field;
+    }
+
+    #[test]
+    fn accepts_valid_fens() {
+        assert_eq!(ParsedFEN::parse(START_FEN).unwrap().to_string(), START_FEN);
+        let short: ParsedFEN = ParsedFEN::parse("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq -").unwrap();
+        assert_eq!(short.to_string(), START_FEN);
+        let en_passant: &str = "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 3";
+        assert_eq!(ParsedFEN::parse(en_passant).unwrap().en_passant, 20);
+    }
+
+    #[test]
+    fn rejects_invalid_fens() {
+        assert_eq!(error_field("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP w KQkq - 0 1"), "board");
+        assert_eq!(error_field("rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"), "board");
+        assert_eq!(error_field("rnbqkbnr/pppppppp/44/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"), "board");
+        assert_eq!(error_field("rnbqkbnr/ppppxppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"), "board");
+        assert_eq!(error_field("rnbqqbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"), "board");
+        assert_eq!(error_field("rnbqkbnP/pppppppp/8/8/8/8/PPPPPPP1/RNBQKBNR w KQq - 0 1"), "board");
+        assert_eq!(error_field("4k3/8/8/8/8/8/4R3/4K3 w - - 0 1"), "board");
+        assert_eq!(error_field("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1"), "color");
+        assert_eq!(error_field("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBN1 w KQkq - 0 1"), "castling");
+        assert_eq!(error_field("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KKkq - 0 1"), "castling");
+        assert_eq!(error_field("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e3 0 1"), "en passant");
+        assert_eq!(error_field("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - x 1"), "halfmove clock");
+        assert_eq!(error_field("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 0"), "fullmove clock");
+        assert_eq!(error_field("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w"), "fen");
+    }
+}
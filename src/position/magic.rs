@@ -0,0 +1,158 @@
+use std::sync::LazyLock;
+
+use super::bitboard::{self, Bitboard, FILE_A, FILE_H, RANK_1, RANK_8};
+
+// Looks up slider attacks with a single multiply and shift: the blockers that matter (the mask, board edges
+// left out) are multiplied by a magic number whose top bits then index a table of precomputed attack sets.
+// Built with BMI2, the index comes from PEXT instead and no magic number is needed.
+#[derive(Clone, Copy, Default)]
+struct Magic {
+    mask: Bitboard,
+    magic: u64,
+    shift: u32,
+    offset: usize, // Start of this square's slice of SliderTables.attacks
+}
+
+impl Magic {
+    #[cfg(not(all(target_arch = "x86_64", target_feature = "bmi2")))]
+    fn index(&self, occupied: Bitboard) -> usize {
+        return self.offset + ((occupied & self.mask).wrapping_mul(self.magic) >> self.shift) as usize;
+    }
+
+    #[cfg(all(target_arch = "x86_64", target_feature = "bmi2"))]
+    fn index(&self, occupied: Bitboard) -> usize {
+        // Safe since the target feature is enabled at compile time
+        return self.offset + unsafe { std::arch::x86_64::_pext_u64(occupied, self.mask) } as usize;
+    }
+}
+
+struct SliderTables {
+    rook: [Magic; 64],
+    bishop: [Magic; 64],
+    attacks: Vec<Bitboard>, // 102400 rook entries followed by 5248 bishop entries
+}
+
+static TABLES: LazyLock<SliderTables> = LazyLock::new(build_tables);
+
+// Built on first use, call it at startup so the first search doesn't pay for it
+pub fn init() {
+    LazyLock::force(&TABLES);
+}
+
+pub fn rook_attacks(square: u8, occupied: Bitboard) -> Bitboard {
+    let tables: &SliderTables = &TABLES;
+    return tables.attacks[tables.rook[square as usize].index(occupied)];
+}
+
+pub fn bishop_attacks(square: u8, occupied: Bitboard) -> Bitboard {
+    let tables: &SliderTables = &TABLES;
+    return tables.attacks[tables.bishop[square as usize].index(occupied)];
+}
+
+// Squares whose occupancy changes the attacks: the empty-board rays minus the edge they run into
+fn relevant_mask(square: u8, slow_attacks: fn(u8, Bitboard) -> Bitboard) -> Bitboard {
+    let rank: Bitboard = RANK_1 << (square & 0b111000);
+    let file: Bitboard = FILE_A << (square & 0b111);
+    let edges: Bitboard = ((RANK_1 | RANK_8) & !rank) | ((FILE_A | FILE_H) & !file);
+    return slow_attacks(square, 0) & !edges;
+}
+
+// xorshift64*, seeded so the same magics come out on every run
+struct Random(u64);
+
+impl Random {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        return self.0.wrapping_mul(0x2545_F491_4F6C_DD1D);
+    }
+
+    // Magics with few set bits are found much faster
+    fn sparse(&mut self) -> u64 {
+        return self.next() & self.next() & self.next();
+    }
+}
+
+fn build_square(magic: &mut Magic, square: u8, slow_attacks: fn(u8, Bitboard) -> Bitboard, attacks: &mut Vec<Bitboard>, random: &mut Random) {
+    magic.mask = relevant_mask(square, slow_attacks);
+    let bits: u32 = bitboard::popcount(magic.mask);
+    magic.shift = 64 - bits;
+    magic.offset = attacks.len();
+    attacks.resize(attacks.len() + (1 << bits), 0);
+
+    // Every subset of the mask with its attack set, enumerated with the carry-rippler trick
+    let mut occupancies: Vec<Bitboard> = Vec::with_capacity(1 << bits);
+    let mut references: Vec<Bitboard> = Vec::with_capacity(1 << bits);
+    let mut subset: Bitboard = 0;
+    loop {
+        occupancies.push(subset);
+        references.push(slow_attacks(square, subset));
+        subset = subset.wrapping_sub(magic.mask) & magic.mask;
+        if(subset == 0) {
+            break;
+        }
+    }
+
+    if(cfg!(all(target_arch = "x86_64", target_feature = "bmi2"))) {
+        for (&occupied, &reference) in occupancies.iter().zip(references.iter()) {
+            attacks[magic.index(occupied)] = reference;
+        }
+        return;
+    }
+
+    // Try random candidates until one maps every subset to a slot that is free or already holds the same attacks
+    let mut epoch: Vec<u32> = vec![0; 1 << bits];
+    let mut attempt: u32 = 0;
+    'search: loop {
+        attempt += 1;
+        magic.magic = random.sparse();
+        if(bitboard::popcount(magic.mask.wrapping_mul(magic.magic) & 0xFF00_0000_0000_0000) < 6) {
+            continue;
+        }
+        for (&occupied, &reference) in occupancies.iter().zip(references.iter()) {
+            let index: usize = magic.index(occupied);
+            let slot: usize = index - magic.offset;
+            if(epoch[slot] != attempt) {
+                epoch[slot] = attempt;
+                attacks[index] = reference;
+            } else if(attacks[index] != reference) {
+                continue 'search;
+            }
+        }
+        return;
+    }
+}
+
+fn build_tables() -> SliderTables {
+    let mut tables: SliderTables = SliderTables {
+        rook: [Magic::default(); 64],
+        bishop: [Magic::default(); 64],
+        attacks: Vec::with_capacity(102400 + 5248),
+    };
+    let mut random: Random = Random(0x9E37_79B9_7F4A_7C15);
+    for square in 0..64 {
+        build_square(&mut tables.rook[square as usize], square, bitboard::ray_rook_attacks, &mut tables.attacks, &mut random);
+    }
+    for square in 0..64 {
+        build_square(&mut tables.bishop[square as usize], square, bitboard::ray_bishop_attacks, &mut tables.attacks, &mut random);
+    }
+    return tables;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_ray_attacks() {
+        let mut random: Random = Random(12345);
+        for square in 0..64 {
+            for _ in 0..200 {
+                let occupied: Bitboard = random.sparse() | random.sparse();
+                assert_eq!(rook_attacks(square, occupied), bitboard::ray_rook_attacks(square, occupied));
+                assert_eq!(bishop_attacks(square, occupied), bitboard::ray_bishop_attacks(square, occupied));
+            }
+        }
+    }
+}
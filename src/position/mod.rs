@@ -1,137 +1,159 @@
+pub mod attacks;
+pub mod bitboard;
+pub mod error;
 pub mod fen;
+pub mod magic;
+pub mod movegen;
+pub mod moves;
+pub mod perft;
 pub mod piece;
+pub mod result;
+pub mod san;
+pub mod see;
+pub mod undo;
+pub mod zobrist;
 
-use self::{fen::ParsedFEN, piece::Piece};
+use self::{bitboard::Bitboards, error::PositionError, fen::ParsedFEN, moves::Move, undo::UndoRecord, zobrist::KEYS};
 
 #[derive(Debug, Clone)]
 pub struct Position {
     pub state: ParsedFEN,
-    pub fen: String,
-    pub old_position: Option<ParsedFEN>,
+    pub bitboards: Bitboards, // Same placement as state.board, kept in sync by make_move and unmake_move
+    pub key: u64, // Zobrist key of state, kept up to date by make_move and unmake_move
+    pub history: Vec<UndoRecord>, // One record per move played since the root, last move on top
+    pub redo_stack: Vec<Move>, // Moves taken back by unmake_move, cleared as soon as make_move plays another move
 }
 
 impl Position {
+    // For FEN strings known to be valid, anything coming from outside should go through from_fen
     pub fn from(fen_string: &str) -> Position {
-        return Position {
-            state: ParsedFEN::from(&fen_string),
-            fen: fen_string.to_string(),
-            old_position: None,
-        };
+        return Position::from_state(ParsedFEN::from(fen_string));
     }
 
+    #[allow(clippy::new_without_default)]
     pub fn new() -> Position {
+        return Position::from(fen::START_FEN);
+    }
+
+    pub fn from_fen(fen_string: &str) -> Result<Position, PositionError> {
+        return Ok(Position::from_state(ParsedFEN::parse(fen_string)?));
+    }
+
+    fn from_state(state: ParsedFEN) -> Position {
         return Position {
-            state: ParsedFEN::from(fen::START_FEN),
-            fen: fen::START_FEN.to_string(),
-            old_position: None,
+            key: zobrist::compute_key(&state.board, state.color, state.castle, state.en_passant),
+            bitboards: Bitboards::from_board(&state.board),
+            state,
+            history: Vec::new(),
+            redo_stack: Vec::new(),
         };
     }
 
-    pub fn make_move(&mut self, origin: &str, target: &str, promotion: &str) -> Result<u8, u8> {
+    pub fn compute_key(&self) -> u64 {
+        return zobrist::compute_key(&self.state.board, self.state.color, self.state.castle, self.state.en_passant);
+    }
 
-        let origin_as_u8: u8 = parse_square(origin);
-        let target_as_u8: u8 = parse_square(target);
+    // Checked entry point for moves coming from outside: UCI, PGN and redo. Replaying the move last taken back
+    // keeps the rest of the redo stack, any other move clears it.
+    pub fn make_move(&mut self, chess_move: Move) -> Result<(), PositionError> {
+        if(!self.is_legal(chess_move)) {
+            return Err(PositionError::IllegalMove {
+                mv: chess_move.to_string(),
+                reason: self.illegal_reason(chess_move),
+            });
+        }
+        self.do_move(chess_move);
+        match(self.redo_stack.last()) {
+            Some(redo_move) if redo_move == &chess_move => {
+                self.redo_stack.pop();
+            }
+            _ => self.redo_stack.clear(),
+        }
+        return Ok(());
+    }
 
-        let origin_row: i8 = ((origin_as_u8 & 0b111000) >> 3).try_into().unwrap();
-        let target_row: i8 = ((target_as_u8 & 0b111000) >> 3).try_into().unwrap();
-        let row_diff: i8 = target_row - origin_row;
+    // Plays a move known to be legal, such as one from generate_legal_moves, without generating moves to check it.
+    // The redo stack is left alone, the search pairs this with undo_move.
+    pub fn do_move(&mut self, chess_move: Move) {
+        debug_assert!(self.is_legal(chess_move), "do_move called with illegal move {}", chess_move);
+        let origin_as_u8: u8 = chess_move.origin();
+        let target_as_u8: u8 = chess_move.target();
 
-        let origin_col: i8 = (origin_as_u8 & 0b111).try_into().unwrap();
-        let target_col: i8 = (target_as_u8 & 0b111).try_into().unwrap();
-        let col_diff: i8 = target_col - origin_col;
+        let origin_row: u8 = origin_as_u8 >> 3;
+        let moving_color: u8 = self.state.board[origin_as_u8 as usize].data & 0b1000;
 
-        let legal_moves: Vec<(u8, u8)> = self.generate_legal_moves();
-        
-        if(!legal_moves.contains(&(origin_as_u8, target_as_u8))) {
-            return Err(1);
+        let captured_square: u8 = match(chess_move.is_en_passant()) {
+            true => (origin_row << 3) | (target_as_u8 & 0b111),
+            false => target_as_u8,
+        };
+        let record: UndoRecord = UndoRecord {
+            chess_move,
+            moved_piece: self.state.board[origin_as_u8 as usize].data,
+            captured_piece: self.state.board[captured_square as usize].data,
+            castle: self.state.castle,
+            en_passant: self.state.en_passant,
+            halfmove_clock: self.state.halfmove_clock,
+            key: self.key,
+        };
+
+        // Castling rights and en passant are hashed back in once they have been updated below
+        let mut key: u64 = self.key ^ KEYS.castle[self.state.castle as usize] ^ zobrist::en_passant_key(&self.state.board, self.state.en_passant, self.state.color);
+        key ^= zobrist::piece_key(record.captured_piece, captured_square);
+
+        // Are we capturing a piece or is this a pawn we're moving?
+        if(chess_move.is_capture() || self.state.board[origin_as_u8 as usize].data & 0b111 == 0b1) {
+            self.state.halfmove_clock = 0;
+        } else {
+            self.state.halfmove_clock = self.state.halfmove_clock.saturating_add(1);
         }
 
-        let old_board: ParsedFEN = self.state.clone();
-        
-        // Is a pawn capturing en passant?
-        if(target_as_u8 == self.state.en_passant) {
-            let modifier: i8 = match(target_row) {
-                5 => -1,
-                _ => 1,
-            };
-            let en_passant_target_row: u8 = (target_row as i8 + modifier).try_into().unwrap();
-            let en_passant_target: u8 = (en_passant_target_row << 3) | (target_as_u8 & 0b111);
-            
-            self.state.board[en_passant_target as usize].data = 0;
+        // Is a pawn capturing en passant? The captured pawn sits beside the origin square
+        if(chess_move.is_en_passant()) {
+            self.remove_piece(captured_square);
         }
 
         // Is a king castling?
-        if((self.state.board[origin_as_u8 as usize].data & 0b111 == 0b110) && (col_diff == 2 || col_diff == -2)) {
-            let origin_rook_col: i8 = match(col_diff) {
-                2 => 7,
-                _ => 0,
+        if(chess_move.is_castle()) {
+            let (origin_rook_col, target_rook_col): (u8, u8) = match(chess_move.flags()) {
+                moves::KING_CASTLE => (7, 5),
+                _ => (0, 3),
             };
-            let target_rook_col: i8 = match(col_diff) {
-                2 => 5,
-                _ => 3,
-            };
-            let origin_rook: u8 = ((origin_row << 3) | origin_rook_col).try_into().unwrap();
-            let target_rook: u8 = ((target_row << 3) | target_rook_col).try_into().unwrap();
+            let origin_rook: u8 = (origin_row << 3) | origin_rook_col;
+            let target_rook: u8 = (origin_row << 3) | target_rook_col;
+
+            let rook_data: u8 = self.remove_piece(origin_rook);
+            key ^= zobrist::piece_key(rook_data, origin_rook) ^ zobrist::piece_key(rook_data, target_rook);
+            self.put_piece(target_rook, rook_data | 0b10000);
+        }
 
-            let castling_invert: u8 = match(self.state.board[origin_rook as usize].data & 0b1000) {
+        // Is a king moving? It loses both of its castling rights
+        if(self.state.board[origin_as_u8 as usize].data & 0b111 == 0b110) {
+            self.state.castle &= match(moving_color) {
                 0b1000 => 0b1100,
                 _ => 0b11,
             };
-
-            self.state.board[target_rook as usize].data = self.state.board[origin_rook as usize].data;
-            self.state.board[origin_rook as usize].data = 0;
-            
-            self.state.castle &= castling_invert;
         }
-        
-        // Is a rook moving?
-        if(self.state.board[origin_as_u8 as usize].data & 0b111 == 0b100) {
-            let mut castling_mask: u8 = match(origin_col) {
-                7 => 0b10,
-                _ => 0b1,
-            };
-            if(self.state.board[origin_as_u8 as usize].data & 0b1000 == 0) {
-                castling_mask <<= 2;
-            }
-
-            castling_mask ^= 0b1111;
 
-            self.state.castle &= castling_mask;
-        }
-
-        // Has a pawn reached the end row?
-        if((self.state.board[origin_as_u8 as usize].data & 0b111 == 0b1) && (target_row == 7 || target_row == 0)) {
-            let mut promotion_as_u8: u8 = piece::Piece::from(&promotion.chars().next().unwrap(), &0).data & 0b111;
-            promotion_as_u8 |= (self.state.board[origin_as_u8 as usize].data & 0b1000);
-            
-            self.state.board[origin_as_u8 as usize].data = promotion_as_u8;
-        }
+        // Is a rook leaving or being captured on its starting square?
+        self.state.castle &= castling_mask(origin_as_u8) & castling_mask(target_as_u8);
 
         // Has a pawn moved 2 squares forward?
-        if((self.state.board[origin_as_u8 as usize].data & 0b111 == 0b1) && (row_diff == 2 || row_diff == -2)) {
-            let modifier: i8 = row_diff / 2;
-
-            let en_passant_row: i8 = origin_row + modifier;
-            let en_passant_row_as_u8: u8 = en_passant_row.try_into().unwrap();
-            
-            let en_passant_square: u8 = (en_passant_row_as_u8 << 3) | (origin_as_u8 & 0b111);
-            self.state.en_passant = en_passant_square;
+        if(chess_move.is_double_push()) {
+            self.state.en_passant = (origin_as_u8 + target_as_u8) / 2;
         } else {
             self.state.en_passant = 64;
         }
 
-        // Are we capturing a piece or is this a pawn we're moving?
-        if(self.state.board[target_as_u8 as usize].data & 0b111 != 0) {
-            self.state.halfmove_clock = 0;
-        } else if(self.state.board[origin_as_u8 as usize].data & 0b111 == 1) {
-            self.state.halfmove_clock = 0;
-        } else {
-            self.state.halfmove_clock += 1;
+        let mut landing_piece: u8 = self.remove_piece(origin_as_u8) | 0b10000; // Has moved
+        // Has a pawn reached the end row?
+        if(chess_move.is_promotion()) {
+            landing_piece = chess_move.promotion() | moving_color | 0b10000;
         }
-
-        self.state.board[origin_as_u8 as usize].data |= 0b10000; // Has moved
-        self.state.board[target_as_u8 as usize].data = self.state.board[origin_as_u8 as usize].data;
-        self.state.board[origin_as_u8 as usize].data = 0;
+        if(!chess_move.is_en_passant() && record.captured_piece != 0) {
+            self.remove_piece(target_as_u8);
+        }
+        self.put_piece(target_as_u8, landing_piece);
+        key ^= zobrist::piece_key(record.moved_piece, origin_as_u8) ^ zobrist::piece_key(landing_piece, target_as_u8);
 
         if(self.state.color) {
             self.state.fullmove_clock += 1;
@@ -139,284 +161,138 @@ impl Position {
 
         self.state.color = !self.state.color;
 
-        self.fen = self.state.to_string();
+        key ^= KEYS.side ^ KEYS.castle[self.state.castle as usize] ^ zobrist::en_passant_key(&self.state.board, self.state.en_passant, self.state.color);
+        self.key = key;
+        debug_assert_eq!(self.key, self.compute_key(), "incremental Zobrist key drifted after {}", chess_move);
+        debug_assert_eq!(self.bitboards, Bitboards::from_board(&self.state.board), "bitboards drifted after {}", chess_move);
 
-        self.old_position = Some(old_board);
-        return Ok(0);
+        self.history.push(record);
     }
 
-    pub fn unmake_move(&mut self) -> Result<u8, u8> {
-        let code: u8;
-        self.state = match(&self.old_position) {
-            Some(old_state) => {
-                code = 0;
-                old_state.clone()
-            },
-            None => {
-                code = 1;
-                self.state.clone()
-            }
-        };
-        self.old_position = None;
-        self.fen = self.state.to_string();
-        if(code == 0) {
-            return Ok(0);
-        } else  {
-            return Err(code);
-        }
+    // Takes back the last move and keeps it for redo_move
+    pub fn unmake_move(&mut self) -> Result<(), PositionError> {
+        let chess_move: Move = self.undo_move()?;
+        self.redo_stack.push(chess_move);
+        return Ok(());
     }
 
-    pub fn generate_legal_moves(&self) -> Vec<(u8, u8)> {
-        let mut all_legal_moves: Vec<(u8, u8)> = Vec::new();
-        let color: u8 = (self.state.color as u8) << 3;
+    // Takes back the last move without recording it for redo_move, returns the move taken back
+    pub fn undo_move(&mut self) -> Result<Move, PositionError> {
+        let record: UndoRecord = match(self.history.pop()) {
+            Some(v) => v,
+            None => return Err(PositionError::NoMoveToUndo),
+        };
+        let chess_move: Move = record.chess_move;
+        let origin_as_u8: u8 = chess_move.origin();
+        let target_as_u8: u8 = chess_move.target();
+        let origin_row: u8 = origin_as_u8 >> 3;
 
-        let mut piece_amounts: u8 = 0;
+        self.state.color = !self.state.color;
+        if(self.state.color) {
+            self.state.fullmove_clock -= 1;
+        }
 
-        for i in 0..self.state.board.len() {
-            let piece: &Piece = &self.state.board[i];
+        self.remove_piece(target_as_u8);
+        self.put_piece(origin_as_u8, record.moved_piece);
 
-            // if the square we're on isn't a piece of our color (= empty or enemy)
-            if(piece.data & 0b1000 != color || piece.data & 0b111 == 0) {
-                continue;
-            } else {
-                piece_amounts += 1;
-            }
+        let captured_square: u8 = match(chess_move.is_en_passant()) {
+            true => (origin_row << 3) | (target_as_u8 & 0b111),
+            false => target_as_u8,
+        };
+        self.put_piece(captured_square, record.captured_piece);
 
-            match(piece.data & 0b111) {
-                // PAWNS
-                1 => {
-                    let row_modifier: isize = ((color >> 3) as isize) * -2 + 1;
-
-                    // Check if we're moving to the square in front of us
-                    if(self.state.board[(i as isize + row_modifier * 8) as usize].data == 0) {
-                        all_legal_moves.push((i as u8, (i as isize + row_modifier * 8) as u8));
-
-                        // Can we move 2 squares forward?
-                        let tmp: usize = (i as isize + row_modifier * 16) as usize;
-                        if(tmp < self.state.board.len()) {
-                            if(self.state.board[tmp].data == 0 && piece.data & 0b10000 == 0) {
-                                all_legal_moves.push((i as u8, (i as isize + row_modifier * 16) as u8));
-                            }   
-                        }
-                    }
-
-                    if((i % 8) != 7) {
-                        let index: usize = (i as isize + row_modifier * 8 + 1) as usize;
-                        let square_at_index = &self.state.board[index];
-                        let piece_color: u8 = square_at_index.data & 0b1000;
-                        if(square_at_index.data & 0b111 != 0 && piece_color != color) {
-                            all_legal_moves.push((i as u8, index as u8));
-                        }
-                    }
-
-                    if((i % 8) != 0) {
-                        let index: usize = (i as isize + row_modifier * 8 - 1) as usize;
-                        let square_at_index: &Piece = &self.state.board[index];
-                        let piece_color: u8 = square_at_index.data & 0b1000;
-                        if(square_at_index.data & 0b111 != 0 && piece_color != color) {
-                            all_legal_moves.push((i as u8, index as u8));
-                        }
-                    }
-                }
-                // KNIGHTS
-                2 => {
-                    let mut move_one: u8 = 64;
-                    let mut move_two: u8 = 64;
-                    let mut move_three: u8 = 64;
-                    let mut move_four: u8 = 64;
-                    let mut move_five: u8 = 64;
-                    let mut move_six: u8 = 64;
-                    let mut move_seven: u8 = 64;
-                    let mut move_eight: u8 = 64;
-
-                    // at least third row
-                    if(16 <= i) {
-                        // not first column
-                        if(i % 8 != 0) {
-                            move_seven = i as u8 - 17;
-                        }
-                        // not last/eighth column
-                        if(i % 8 != 7) {
-                            move_eight = i as u8 - 15;
-                        }
-                    }
-
-                    // at least second row
-                    if(10 <= i) {
-                        // not first nor second column
-                        if(i % 8 != 0 && i % 8 != 1) {
-                            move_five = i as u8 - 10;
-                        }
-                        // not seventh or last/eighth column
-                        if(i % 8 != 6 && i % 8 != 7) {
-                            move_six = i as u8 - 6;
-                        }
-                    }
-
-                    // before third-to-last row
-                    if(i <= 47) {
-                        // not first column
-                        if(i % 8 != 0) {
-                            move_one = i as u8 + 15;
-                        }
-                        // not last/eighth column
-                        if(i % 8 != 7) {
-                            move_two = i as u8 + 17;
-                        }
-                    }
-
-                    // before second-to-last row
-                    if(i <= 53) {
-                        // not last/eigth nor seventh column
-                        if(i % 8 != 6 && i % 8 != 7) {
-                            move_four = i as u8 + 10;
-                        }
-                        // not first/second column
-                        if(i % 8 != 0 && i % 8 != 1) {
-                            move_three = i as u8 + 6;
-                        }
-                    }
-
-                    // checks for same-colored pieces
-                    if(move_one != 64) {
-                        if(self.state.board[move_one as usize].data & 0b111 == 0 || self.state.board[move_one as usize].data & 0b1000 != color) {
-                            all_legal_moves.push((i as u8, move_one));
-                        }
-                    }
-                    if(move_two != 64) {
-                        if(self.state.board[move_two as usize].data & 0b111 == 0 || self.state.board[move_two as usize].data & 0b1000 != color) {
-                            all_legal_moves.push((i as u8, move_two));
-                        }
-                    }
-                    if(move_three != 64) {
-                        if(self.state.board[move_three as usize].data & 0b111 == 0 || self.state.board[move_three as usize].data & 0b1000 != color) {
-                            all_legal_moves.push((i as u8, move_three));
-                        }
-                    }
-                    if(move_four != 64) {
-                        if(self.state.board[move_four as usize].data & 0b111 == 0 || self.state.board[move_four as usize].data & 0b1000 != color) {
-                            all_legal_moves.push((i as u8, move_four));
-                        }
-                    }
-                    if(move_five != 64) {
-                        if(self.state.board[move_five as usize].data & 0b111 == 0 || self.state.board[move_five as usize].data & 0b1000 != color) {
-                            all_legal_moves.push((i as u8, move_five));
-                        }
-                    }
-                    if(move_six != 64) {
-                        if(self.state.board[move_six as usize].data & 0b111 == 0 || self.state.board[move_six as usize].data & 0b1000 != color) {
-                            all_legal_moves.push((i as u8, move_six));
-                        }
-                    }
-                    if(move_seven != 64) {
-                        if(self.state.board[move_seven as usize].data & 0b111 == 0 || self.state.board[move_seven as usize].data & 0b1000 != color) {
-                            all_legal_moves.push((i as u8, move_seven));
-                        }
-                    }
-                    if(move_eight != 64) {
-                        if(self.state.board[move_eight as usize].data & 0b111 == 0 || self.state.board[move_eight as usize].data & 0b1000 != color) {
-                            all_legal_moves.push((i as u8, move_eight));
-                        }
-                    }
-                }
-                // BISHOPS
-                3 => {
-                    all_legal_moves.append(&mut self.generate_sliding_move(9, i));
-                    all_legal_moves.append(&mut self.generate_sliding_move(7, i));
-                    all_legal_moves.append(&mut self.generate_sliding_move(-7, i));
-                    all_legal_moves.append(&mut self.generate_sliding_move(-9, i));
-                }
-                // ROOKS
-                4 => {
-                    all_legal_moves.append(&mut self.generate_sliding_move(8, i));
-                    all_legal_moves.append(&mut self.generate_sliding_move(1, i));
-                    all_legal_moves.append(&mut self.generate_sliding_move(-1, i));
-                    all_legal_moves.append(&mut self.generate_sliding_move(-8, i));
-                }
-                // QUEENS
-                5 => {
-                    all_legal_moves.append(&mut self.generate_sliding_move(8, i));
-                    all_legal_moves.append(&mut self.generate_sliding_move(1, i));
-                    all_legal_moves.append(&mut self.generate_sliding_move(-1, i));
-                    all_legal_moves.append(&mut self.generate_sliding_move(-8, i));
-                    all_legal_moves.append(&mut self.generate_sliding_move(9, i));
-                    all_legal_moves.append(&mut self.generate_sliding_move(7, i));
-                    all_legal_moves.append(&mut self.generate_sliding_move(-7, i));
-                    all_legal_moves.append(&mut self.generate_sliding_move(-9, i));
-                }
-                // KINGS
-                6 => {
-                    all_legal_moves.append(&mut self.generate_one_move(8, i));
-                    all_legal_moves.append(&mut self.generate_one_move(1, i));
-                    all_legal_moves.append(&mut self.generate_one_move(-1, i));
-                    all_legal_moves.append(&mut self.generate_one_move(-8, i));
-                    all_legal_moves.append(&mut self.generate_one_move(9, i));
-                    all_legal_moves.append(&mut self.generate_one_move(7, i));
-                    all_legal_moves.append(&mut self.generate_one_move(-7, i));
-                    all_legal_moves.append(&mut self.generate_one_move(-9, i));
-                }
-                _ => ()
-            }
+        // Put the castling rook back, it had never moved since castling was still allowed
+        if(chess_move.is_castle()) {
+            let (origin_rook_col, target_rook_col): (u8, u8) = match(chess_move.flags()) {
+                moves::KING_CASTLE => (7, 5),
+                _ => (0, 3),
+            };
+            let origin_rook: u8 = (origin_row << 3) | origin_rook_col;
+            let target_rook: u8 = (origin_row << 3) | target_rook_col;
 
-            if(piece_amounts > 15) {
-                // Stop the loop short if we have visited all pieces of a single color
-                break;
-            }
+            let rook_data: u8 = self.remove_piece(target_rook);
+            self.put_piece(origin_rook, rook_data & !0b10000);
         }
 
-        return all_legal_moves;
+        self.state.castle = record.castle;
+        self.state.en_passant = record.en_passant;
+        self.state.halfmove_clock = record.halfmove_clock;
+        self.key = record.key;
+        debug_assert_eq!(self.bitboards, Bitboards::from_board(&self.state.board), "bitboards drifted undoing {}", chess_move);
+        return Ok(chess_move);
     }
 
-    fn generate_one_move(&self, offset: isize, origin_square: usize) -> Vec<(u8, u8)> {
-        let mut arr: Vec<(u8, u8)> = Vec::new();
-        let tmp_square: isize = origin_square as isize + offset;
-        let modulo: isize = origin_square as isize % 8;
-        if(!(modulo + 1 == tmp_square % 8 || modulo - 1 == tmp_square % 8 || modulo == tmp_square % 8)) {
-            return arr;
-        }
-        if(tmp_square < 0 || tmp_square >= 64) {
-            return arr;
-        }
-        if(self.state.board[tmp_square as usize].data & 0b111 != 0) {
-            if((self.state.board[tmp_square as usize].data & 0b1000) >> 3 != self.state.color as u8) {
-                arr.push((origin_square as u8, tmp_square as u8));
-                return arr;
-            } else {
-                return arr;
-            }
+    // Plays back the last move taken back by unmake_move
+    pub fn redo_move(&mut self) -> Result<(), PositionError> {
+        let chess_move: Move = match(self.redo_stack.last()) {
+            Some(v) => *v,
+            None => return Err(PositionError::NoMoveToRedo),
+        };
+        return self.make_move(chess_move);
+    }
+
+    // Passes the turn for null-move pruning. The halfmove clock restarts so no repetition is
+    // found across the null move, and the redo stack is left alone since it's never replayed.
+    pub fn make_null_move(&mut self) {
+        self.history.push(UndoRecord {
+            chess_move: Move::NULL,
+            moved_piece: 0,
+            captured_piece: 0,
+            castle: self.state.castle,
+            en_passant: self.state.en_passant,
+            halfmove_clock: self.state.halfmove_clock,
+            key: self.key,
+        });
+        self.key ^= KEYS.side ^ zobrist::en_passant_key(&self.state.board, self.state.en_passant, self.state.color);
+        self.state.en_passant = 64;
+        self.state.halfmove_clock = 0;
+        if(self.state.color) {
+            self.state.fullmove_clock += 1;
         }
-        arr.push((origin_square as u8, tmp_square as u8));
-        return arr;
+        self.state.color = !self.state.color;
+        debug_assert_eq!(self.key, self.compute_key(), "incremental Zobrist key drifted after a null move");
     }
 
-    fn generate_sliding_move(&self, offset: isize, origin_square: usize) -> Vec<(u8, u8)> {
-        let mut arr: Vec<(u8, u8)> = Vec::new();
-        let mut tmp_square: isize = origin_square as isize;
-        let mut modulo: isize = tmp_square % 8;
-        while(modulo + 1 == tmp_square % 8 || modulo - 1 == tmp_square % 8 || modulo == tmp_square % 8) {
-            modulo = tmp_square % 8;
-            if(tmp_square < 0 || tmp_square >= 64) {
-                break;
-            }
-            if(self.state.board[tmp_square as usize].data & 0b111 != 0) {
-                if(tmp_square == origin_square as isize) {
-                    tmp_square += offset;
-                    continue;
-                }
-                if((self.state.board[tmp_square as usize].data & 0b1000) >> 3 != self.state.color as u8) {
-                    arr.push((origin_square as u8, tmp_square as u8));
-                    break;
-                } else {
-                    break;
-                }
-            }
-            if(tmp_square != origin_square as isize) {
-                arr.push((origin_square as u8, tmp_square as u8));
-            }
-            tmp_square += offset;
+    pub fn unmake_null_move(&mut self) {
+        let record: UndoRecord = self.history.pop().expect("a null move was made");
+        debug_assert!(record.chess_move == Move::NULL, "unmake_null_move called after {}", record.chess_move);
+        self.state.color = !self.state.color;
+        if(self.state.color) {
+            self.state.fullmove_clock -= 1;
         }
-        return arr;
+        self.state.en_passant = record.en_passant;
+        self.state.halfmove_clock = record.halfmove_clock;
+        self.key = record.key;
     }
+
+    pub fn fen(&self) -> String {
+        return self.state.to_string();
+    }
+
+    // Mailbox and bitboard edits go through these two so that they can't drift apart
+    fn put_piece(&mut self, square: u8, data: u8) {
+        self.state.board[square as usize].data = data;
+        self.bitboards.toggle(square, data);
+    }
+
+    fn remove_piece(&mut self, square: u8) -> u8 {
+        let data: u8 = self.state.board[square as usize].data;
+        self.state.board[square as usize].data = 0;
+        self.bitboards.toggle(square, data);
+        return data;
+    }
+}
+
+
+// Castling rights that survive a move from or to the given square
+fn castling_mask(square: u8) -> u8 {
+    return match(square) {
+        0 => 0b1011,
+        7 => 0b0111,
+        56 => 0b1110,
+        63 => 0b1101,
+        _ => 0b1111,
+    };
 }
 
 pub fn to_square(square: &u8) -> String {
@@ -456,4 +332,52 @@ pub fn parse_square(square: &str) -> u8 {
         'h' => 7,
         _ => 0,
     });
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(position: &mut Position, moves: &str) {
+        for uci in moves.split_whitespace() {
+            position.make_move(Move::from_uci(uci, position).unwrap()).unwrap();
+        }
+    }
+
+    #[test]
+    fn redoes_moves() {
+        let mut position: Position = Position::new();
+        play(&mut position, "e2e4 e7e5 g1f3");
+        let (fen, key): (String, u64) = (position.fen(), position.key);
+        position.unmake_move().unwrap();
+        position.unmake_move().unwrap();
+        assert_eq!(position.redo_stack.len(), 2);
+        position.redo_move().unwrap();
+        position.redo_move().unwrap();
+        assert_eq!((position.fen(), position.key), (fen, key));
+        assert!(position.redo_move().is_err());
+
+        // Playing the move that was taken back keeps the rest of the stack, another move clears it
+        position.unmake_move().unwrap();
+        position.unmake_move().unwrap();
+        play(&mut position, "e7e5");
+        assert_eq!(position.redo_stack.len(), 1);
+        play(&mut position, "b1c3");
+        assert!(position.redo_stack.is_empty());
+        assert!(position.redo_move().is_err());
+    }
+
+    #[test]
+    fn undoes_without_recording() {
+        let mut position: Position = Position::new();
+        play(&mut position, "e2e4");
+        position.unmake_move().unwrap();
+        let (fen, key): (String, u64) = (position.fen(), position.key);
+        for chess_move in position.generate_legal_moves() {
+            position.do_move(chess_move);
+            assert_eq!(position.undo_move().unwrap(), chess_move);
+        }
+        assert_eq!((position.fen(), position.key), (fen, key));
+        assert_eq!(position.redo_stack, vec![Move::from_uci("e2e4", &position).unwrap()]);
+        assert!(matches!(position.undo_move(), Err(PositionError::NoMoveToUndo)));
+    }
+}
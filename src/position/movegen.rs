@@ -0,0 +1,236 @@
+use super::bitboard::{self, Bitboard, Squares, KING_ATTACKS, KNIGHT_ATTACKS, PAWN_ATTACKS};
+use super::{moves, moves::Move, to_square, Position};
+
+impl Position {
+    pub fn generate_legal_moves(&self) -> Vec<Move> {
+        return self.filter_legal(self.generate_pseudo_legal_moves(false));
+    }
+
+    // Legal captures and promotions only, for the quiescence search
+    pub fn generate_captures(&self) -> Vec<Move> {
+        return self.filter_legal(self.generate_pseudo_legal_moves(true));
+    }
+
+    // Out of check, a move by a piece that isn't pinned can't expose the king, unless it's the king itself
+    // or an en passant capture taking two pieces off the same rank
+    fn filter_legal(&self, mut pseudo_legal_moves: Vec<Move>) -> Vec<Move> {
+        let in_check: bool = self.in_check();
+        let pinned: Bitboard = self.pinned_pieces();
+        let king_square: u8 = self.bitboards.king_square(self.state.color);
+        pseudo_legal_moves.retain(|&chess_move| {
+            let needs_check: bool = in_check
+                || pinned & (1 << chess_move.origin()) != 0
+                || chess_move.origin() == king_square
+                || chess_move.is_en_passant();
+            return !needs_check || !self.leaves_king_in_check(chess_move);
+        });
+        return pseudo_legal_moves;
+    }
+
+    pub fn is_legal(&self, chess_move: Move) -> bool {
+        return self.generate_pseudo_legal_moves(false).contains(&chess_move) && !self.leaves_king_in_check(chess_move);
+    }
+
+    // Explains why a move isn't in the legal move list, for error messages
+    pub fn illegal_reason(&self, chess_move: Move) -> String {
+        let origin_square: String = to_square(&chess_move.origin());
+        let piece_data: u8 = self.state.board[chess_move.origin() as usize].data;
+        if(piece_data & 0b111 == 0) {
+            return format!("there is no piece on {}", origin_square);
+        }
+        if((piece_data & 0b1000 != 0) != self.state.color) {
+            return format!("the piece on {} belongs to the side not to move", origin_square);
+        }
+        let pseudo_legal_match: Option<Move> = self.generate_pseudo_legal_moves(false)
+            .into_iter()
+            .find(|candidate| candidate.origin() == chess_move.origin() && candidate.target() == chess_move.target());
+        return match(pseudo_legal_match) {
+            Some(candidate) if self.leaves_king_in_check(candidate) => String::from("it leaves the king in check"),
+            Some(_) => String::from("the promotion piece is missing or wrong"),
+            None => format!("the piece on {} can't move to {}", origin_square, to_square(&chess_move.target())),
+        };
+    }
+
+    // Looks for an attack on our king once the move is played, which covers pins, single and double checks,
+    // king steps into attacked squares and en passant discovered checks
+    fn leaves_king_in_check(&self, chess_move: Move) -> bool {
+        let origin: u8 = chess_move.origin();
+        let target: u8 = chess_move.target();
+        let captured_square: u8 = match(chess_move.is_en_passant()) {
+            true => (origin & 0b111000) | (target & 0b111),
+            false => target,
+        };
+        let captured: Bitboard = 1 << captured_square;
+        let occupied: Bitboard = (self.bitboards.occupied() & !(1 << origin) & !captured) | (1 << target);
+        let king_square: u8 = match(self.state.board[origin as usize].data & 0b111) {
+            0b110 => target,
+            _ => self.bitboards.king_square(self.state.color),
+        };
+        return self.bitboards.attackers(king_square, !self.state.color, occupied) & !captured != 0;
+    }
+
+    // Captures_only keeps captures, en passant and promotions, the moves the quiescence search looks at
+    fn generate_pseudo_legal_moves(&self, captures_only: bool) -> Vec<Move> {
+        let mut moves: Vec<Move> = Vec::with_capacity(64);
+        let color: bool = self.state.color;
+        let own: Bitboard = self.bitboards.colors[color as usize];
+        let enemies: Bitboard = self.bitboards.colors[!color as usize];
+        let occupied: Bitboard = own | enemies;
+        let targets: Bitboard = match(captures_only) {
+            true => enemies,
+            false => !own,
+        };
+
+        self.generate_pawn_moves(&mut moves, captures_only);
+
+        for piece_type in 0b010..=0b110 {
+            for origin in Squares(self.bitboards.of(piece_type, color)) {
+                let attacks: Bitboard = match(piece_type) {
+                    0b010 => KNIGHT_ATTACKS[origin as usize],
+                    0b011 => bitboard::bishop_attacks(origin, occupied),
+                    0b100 => bitboard::rook_attacks(origin, occupied),
+                    0b101 => bitboard::queen_attacks(origin, occupied),
+                    _ => KING_ATTACKS[origin as usize],
+                };
+                for target in Squares(attacks & targets) {
+                    let flags: u8 = match(enemies & (1 << target) != 0) {
+                        true => moves::CAPTURE,
+                        false => moves::QUIET,
+                    };
+                    moves.push(Move::new(origin, target, flags));
+                }
+            }
+        }
+
+        if(!captures_only) {
+            self.generate_castling_moves(&mut moves);
+        }
+        return moves;
+    }
+
+    fn generate_pawn_moves(&self, moves: &mut Vec<Move>, captures_only: bool) {
+        let color: bool = self.state.color;
+        let pawns: Bitboard = self.bitboards.of(0b001, color);
+        let enemies: Bitboard = self.bitboards.colors[!color as usize];
+        let empty: Bitboard = !self.bitboards.occupied();
+        let (forward, promotion_rank, double_push_rank): (i8, Bitboard, Bitboard) = match(color) {
+            false => (8, bitboard::RANK_8, bitboard::RANK_3),
+            true => (-8, bitboard::RANK_1, bitboard::RANK_6),
+        };
+        let push = |bitboard: Bitboard| -> Bitboard {
+            return match(color) {
+                false => bitboard << 8,
+                true => bitboard >> 8,
+            };
+        };
+
+        let single_pushes: Bitboard = push(pawns) & empty;
+        for target in Squares(single_pushes & promotion_rank) {
+            push_promotions(moves, (target as i8 - forward) as u8, target, false);
+        }
+        if(!captures_only) {
+            for target in Squares(single_pushes & !promotion_rank) {
+                moves.push(Move::new((target as i8 - forward) as u8, target, moves::QUIET));
+            }
+            // Only pawns that stepped onto the third rank from their starting rank can go on
+            for target in Squares(push(single_pushes & double_push_rank) & empty) {
+                moves.push(Move::new((target as i8 - 2 * forward) as u8, target, moves::DOUBLE_PUSH));
+            }
+        }
+
+        for origin in Squares(pawns) {
+            let attacks: Bitboard = PAWN_ATTACKS[color as usize][origin as usize];
+            for target in Squares(attacks & enemies) {
+                match(promotion_rank & (1 << target) != 0) {
+                    true => push_promotions(moves, origin, target, true),
+                    false => moves.push(Move::new(origin, target, moves::CAPTURE)),
+                }
+            }
+            if(self.state.en_passant < 64 && attacks & (1 << self.state.en_passant) != 0) {
+                moves.push(Move::new(origin, self.state.en_passant, moves::EN_PASSANT));
+            }
+        }
+    }
+
+    // The king can't start in or pass through check, landing in check is left to the legality filter
+    fn generate_castling_moves(&self, moves: &mut Vec<Move>) {
+        let color: bool = self.state.color;
+        let (home_square, kingside_right, queenside_right): (u8, u8, u8) = match(color) {
+            false => (4, 0b1000, 0b0100),
+            true => (60, 0b0010, 0b0001),
+        };
+        if(self.state.castle & (kingside_right | queenside_right) == 0 || self.bitboards.king_square(color) != home_square) {
+            return;
+        }
+        if(self.is_square_attacked(home_square, !color)) {
+            return;
+        }
+        let occupied: Bitboard = self.bitboards.occupied();
+        let rooks: Bitboard = self.bitboards.of(0b100, color);
+
+        if(self.state.castle & kingside_right != 0
+            && occupied & (0b11 << (home_square + 1)) == 0
+            && rooks & (1 << (home_square + 3)) != 0
+            && !self.is_square_attacked(home_square + 1, !color)) {
+            moves.push(Move::new(home_square, home_square + 2, moves::KING_CASTLE));
+        }
+
+        if(self.state.castle & queenside_right != 0
+            && occupied & (0b111 << (home_square - 3)) == 0
+            && rooks & (1 << (home_square - 4)) != 0
+            && !self.is_square_attacked(home_square - 1, !color)) {
+            moves.push(Move::new(home_square, home_square - 2, moves::QUEEN_CASTLE));
+        }
+    }
+}
+
+fn push_promotions(moves: &mut Vec<Move>, origin: u8, target: u8, is_capture: bool) {
+    for promotion_type in [0b101, 0b010, 0b100, 0b011] {
+        moves.push(Move::new_promotion(origin, target, promotion_type, is_capture));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Walks every line depth plies deep and compares the capture generator with the captures and promotions of
+    // the legal moves at each node. Returns how many of the nodes were in check.
+    fn compare_captures(position: &mut Position, depth: u32) -> u64 {
+        let mut expected: Vec<u16> = position.generate_legal_moves()
+            .into_iter()
+            .filter(|chess_move| chess_move.is_capture() || chess_move.is_promotion())
+            .map(|chess_move| chess_move.data)
+            .collect::<Vec<u16>>();
+        let mut captures: Vec<u16> = position.generate_captures().into_iter().map(|chess_move| chess_move.data).collect::<Vec<u16>>();
+        expected.sort_unstable();
+        captures.sort_unstable();
+        assert_eq!(captures, expected, "captures of {}", position.fen());
+
+        let mut checks: u64 = position.in_check() as u64;
+        if(depth > 0) {
+            for chess_move in position.generate_legal_moves() {
+                position.do_move(chess_move);
+                checks += compare_captures(position, depth - 1);
+                position.undo_move().expect("a move was just made");
+            }
+        }
+        return checks;
+    }
+
+    #[test]
+    fn generates_captures() {
+        let fens: [&str; 4] = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            // White is in check from the bishop on b6, with promotions on both sides
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        ];
+        let mut checks: u64 = 0;
+        for fen in fens {
+            checks += compare_captures(&mut Position::from(fen), 2);
+        }
+        assert!(checks > 100);
+    }
+}
@@ -0,0 +1,191 @@
+use super::{error::PositionError, Position};
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct Move {
+    pub data: u16, // FFFFTTTTTTOOOOOO where F = flags, T = target square, O = origin square
+}
+
+/*
+    Flag table
+    - 0000 = quiet move
+    - 0001 = double pawn push
+    - 0010 = kingside castle
+    - 0011 = queenside castle
+    - 0100 = capture
+    - 0101 = en passant capture
+    - 1PPP = promotion where the 0100 bit marks a capture and the last 2 bits give the piece : 00 = knight, 01 = bishop, 10 = rook, 11 = queen
+ */
+
+pub const QUIET: u8 = 0b0000;
+pub const DOUBLE_PUSH: u8 = 0b0001;
+pub const KING_CASTLE: u8 = 0b0010;
+pub const QUEEN_CASTLE: u8 = 0b0011;
+pub const CAPTURE: u8 = 0b0100;
+pub const EN_PASSANT: u8 = 0b0101;
+pub const PROMOTION: u8 = 0b1000;
+
+impl Move {
+    pub const NULL: Move = Move { data: 0 };
+
+    pub fn new(origin: u8, target: u8, flags: u8) -> Move {
+        return Move {
+            data: (origin as u16 & 0b111111) | ((target as u16 & 0b111111) << 6) | ((flags as u16 & 0b1111) << 12),
+        };
+    }
+
+    // promotion_type uses the piece encoding of Piece.data (0b010 = knight ..= 0b101 = queen)
+    pub fn new_promotion(origin: u8, target: u8, promotion_type: u8, is_capture: bool) -> Move {
+        let mut flags: u8 = PROMOTION | (promotion_type - 0b010);
+        if(is_capture) {
+            flags |= CAPTURE;
+        }
+        return Move::new(origin, target, flags);
+    }
+
+    pub fn origin(&self) -> u8 {
+        return (self.data & 0b111111) as u8;
+    }
+
+    pub fn target(&self) -> u8 {
+        return ((self.data >> 6) & 0b111111) as u8;
+    }
+
+    pub fn flags(&self) -> u8 {
+        return (self.data >> 12) as u8;
+    }
+
+    pub fn is_capture(&self) -> bool {
+        return self.flags() & CAPTURE != 0;
+    }
+
+    pub fn is_promotion(&self) -> bool {
+        return self.flags() & PROMOTION != 0;
+    }
+
+    pub fn is_castle(&self) -> bool {
+        return self.flags() == KING_CASTLE || self.flags() == QUEEN_CASTLE;
+    }
+
+    pub fn is_en_passant(&self) -> bool {
+        return self.flags() == EN_PASSANT;
+    }
+
+    pub fn is_double_push(&self) -> bool {
+        return self.flags() == DOUBLE_PUSH;
+    }
+
+    // Piece type the pawn turns into, 0 when the move isn't a promotion
+    pub fn promotion(&self) -> u8 {
+        if(!self.is_promotion()) {
+            return 0;
+        }
+        return (self.flags() & 0b11) + 0b010;
+    }
+
+    // Parses UCI long algebraic notation by matching it against the legal moves, so flags come out right
+    pub fn from_uci(move_string: &str, position: &Position) -> Result<Move, PositionError> {
+        let lowercase: String = move_string.to_ascii_lowercase();
+        if(!lowercase.is_ascii() || (lowercase.len() != 4 && lowercase.len() != 5)) {
+            return Err(PositionError::IllegalMove {
+                mv: move_string.to_string(),
+                reason: String::from("expected an origin, a target and an optional promotion piece, e.g. e7e8q"),
+            });
+        }
+        let origin: u8 = try_parse_square(&lowercase[0..2])?;
+        let target: u8 = try_parse_square(&lowercase[2..4])?;
+        if(lowercase.len() == 5 && !"nbrq".contains(&lowercase[4..5])) {
+            return Err(PositionError::IllegalMove {
+                mv: move_string.to_string(),
+                reason: format!("'{}' isn't a promotion piece", &lowercase[4..5]),
+            });
+        }
+
+        let legal_move: Option<Move> = position.generate_legal_moves()
+            .into_iter()
+            .find(|legal_move| legal_move.to_string() == lowercase);
+        return match(legal_move) {
+            Some(v) => Ok(v),
+            None => Err(PositionError::IllegalMove {
+                mv: move_string.to_string(),
+                reason: position.illegal_reason(Move::new(origin, target, QUIET)),
+            }),
+        };
+    }
+}
+
+// Strict counterpart of parse_square for text coming from outside
+pub fn try_parse_square(square: &str) -> Result<u8, PositionError> {
+    let parts: Vec<char> = square.chars().collect::<Vec<char>>();
+    if(parts.len() != 2 || !('a'..='h').contains(&parts[0]) || !('1'..='8').contains(&parts[1])) {
+        return Err(PositionError::InvalidSquare(square.to_string()));
+    }
+    return Ok(super::parse_square(square));
+}
+
+// UCI long algebraic notation, e.g. e2e4, e7e8q or 0000 for the null move
+impl std::fmt::Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if(self == &Move::NULL) {
+            return write!(f, "0000");
+        }
+        let promotion: &str = match(self.promotion()) {
+            0b010 => "n",
+            0b011 => "b",
+            0b100 => "r",
+            0b101 => "q",
+            _ => "",
+        };
+        return write!(f, "{}{}{}", super::to_square(&self.origin()), super::to_square(&self.target()), promotion);
+    }
+}
+
+impl std::fmt::Debug for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Move {{ {}, flags: 0b{:04b} }}",
+            self,
+            self.flags()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_uci() {
+        for fen in [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq - 0 1",
+            "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N w - - 0 1",
+            "n1n5/PPPk4/8/8/8/8/4Kppp/5N1N b - - 0 1",
+        ] {
+            let position: Position = Position::from(fen);
+            for chess_move in position.generate_legal_moves() {
+                assert_eq!(Move::from_uci(&chess_move.to_string(), &position).unwrap(), chess_move, "{}", fen);
+                assert_eq!(Move::from_uci(&chess_move.to_string().to_ascii_uppercase(), &position).unwrap(), chess_move, "{}", fen);
+            }
+        }
+        let kiwipete: Position = Position::from("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+        assert_eq!(Move::from_uci("e1g1", &kiwipete).unwrap().flags(), KING_CASTLE);
+        assert_eq!(Move::from_uci("e1c1", &kiwipete).unwrap().flags(), QUEEN_CASTLE);
+        let promotions: Position = Position::from("n1n5/PPPk4/8/8/8/8/4Kppp/5N1N w - - 0 1");
+        assert_eq!(Move::from_uci("b7a8n", &promotions).unwrap().flags(), PROMOTION | CAPTURE);
+        assert_eq!(Move::from_uci("b7b8r", &promotions).unwrap().promotion(), 0b100);
+        assert_eq!(Move::NULL.to_string(), "0000");
+    }
+
+    #[test]
+    fn rejects_malformed_uci() {
+        let position: Position = Position::from("n1n5/PPPk4/8/8/8/8/4Kppp/5N1N w - - 0 1");
+        for malformed in ["i2e4", "e9e4", "e2e0", "0000", "-e2e"] {
+            assert!(matches!(Move::from_uci(malformed, &position), Err(PositionError::InvalidSquare(_))), "{}", malformed);
+        }
+        // Wrong lengths, non-ASCII text, bad promotion pieces and moves that aren't legal here
+        for malformed in ["", "e2", "e2e4e5", "b7b8", "b7b8k", "b7b8qq", "é7b8", "b7b8é", "e2e4", "a8b6", "c7c8q"] {
+            assert!(matches!(Move::from_uci(malformed, &position), Err(PositionError::IllegalMove { .. })), "{}", malformed);
+        }
+    }
+}
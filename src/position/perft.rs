@@ -0,0 +1,101 @@
+use super::{moves::Move, Position};
+
+impl Position {
+    // Counts the leaf nodes of the legal move tree, the last ply is counted without being played
+    pub fn perft(&mut self, depth: u32) -> u64 {
+        if(depth == 0) {
+            return 1;
+        }
+        let legal_moves: Vec<Move> = self.generate_legal_moves();
+        if(depth == 1) {
+            return legal_moves.len() as u64;
+        }
+        let mut nodes: u64 = 0;
+        for chess_move in legal_moves {
+            nodes += self.perft_child(chess_move, depth - 1);
+        }
+        return nodes;
+    }
+
+    // Perft split by root move, the way Stockfish prints "go perft"
+    pub fn divide(&mut self, depth: u32) -> Vec<(Move, u64)> {
+        let mut counts: Vec<(Move, u64)> = Vec::new();
+        if(depth == 0) {
+            return counts;
+        }
+        for chess_move in self.generate_legal_moves() {
+            let nodes: u64 = self.perft_child(chess_move, depth - 1);
+            counts.push((chess_move, nodes));
+        }
+        return counts;
+    }
+
+    fn perft_child(&mut self, chess_move: Move, depth: u32) -> u64 {
+        self.do_move(chess_move);
+        let nodes: u64 = self.perft(depth);
+        self.undo_move().expect("a move was just made");
+        return nodes;
+    }
+}
+
+pub fn print_divide(position: &mut Position, depth: u32) {
+    let start: std::time::Instant = std::time::Instant::now();
+    let mut total: u64 = 0;
+    for (chess_move, nodes) in position.divide(depth) {
+        println!("{}: {}", chess_move, nodes);
+        total += nodes;
+    }
+    let elapsed_ms: u128 = start.elapsed().as_millis();
+    println!();
+    println!("Nodes searched: {}", total);
+    println!("Time: {} ms", elapsed_ms);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Position;
+
+    // Node counts from https://www.chessprogramming.org/Perft_Results
+    fn check(fen: &str, expected: &[u64]) {
+        let mut position: Position = Position::from(fen);
+        for (depth, nodes) in expected.iter().enumerate() {
+            assert_eq!(position.perft(depth as u32 + 1), *nodes, "perft({}) of {}", depth + 1, fen);
+        }
+        assert_eq!(position.fen(), fen, "perft must leave the position untouched");
+    }
+
+    #[test]
+    fn startpos() {
+        check("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", &[20, 400, 8902, 197281, 4865609]);
+    }
+
+    #[test]
+    fn kiwipete() {
+        check("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", &[48, 2039, 97862, 4085603]);
+    }
+
+    #[test]
+    fn position_3() {
+        check("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", &[14, 191, 2812, 43238, 674624]);
+    }
+
+    #[test]
+    fn position_4() {
+        check("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1", &[6, 264, 9467, 422333]);
+    }
+
+    #[test]
+    fn position_4_mirrored() {
+        check("r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1", &[6, 264, 9467, 422333]);
+    }
+
+    #[test]
+    fn position_5() {
+        check("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8", &[44, 1486, 62379, 2103487]);
+    }
+
+    #[test]
+    fn position_6() {
+        check("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10", &[46, 2079, 89890, 3894594]);
+    }
+}
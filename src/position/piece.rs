@@ -11,6 +11,7 @@ pub struct Piece {
  */
 
 impl Piece {
+    #[allow(clippy::clone_on_copy, clippy::assign_op_pattern)]
     pub fn from(piece_char: &char, position: &u8) -> Piece {
         let mut data: u8 = 0;
         let pos: u8 = position.clone();
@@ -32,6 +33,7 @@ impl Piece {
         };
     }
 
+    #[allow(clippy::inherent_to_string, clippy::needless_borrow)]
     pub fn to_string(&self) -> String {
         let final_string: String = String::from(match((&self).data & 0b111) {
             0b001 => "p",
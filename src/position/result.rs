@@ -0,0 +1,180 @@
+use super::Position;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GameResult {
+    Ongoing,
+    Checkmate,
+    Stalemate,
+    FiftyMoveRule, // Claimable draw, 100 plies without a capture or a pawn move
+    ThreefoldRepetition, // Claimable draw
+    FivefoldRepetition, // Automatic draw
+    SeventyFiveMoveRule, // Automatic draw, 150 plies without a capture or a pawn move
+    InsufficientMaterial,
+}
+
+impl GameResult {
+    // PGN result tag, color is the side to move in the final position (false = WHITE, true = BLACK)
+    pub fn score(&self, color: bool) -> &'static str {
+        return match(self) {
+            GameResult::Ongoing => "*",
+            GameResult::Checkmate => match(color) {
+                false => "0-1",
+                true => "1-0",
+            },
+            _ => "1/2-1/2",
+        };
+    }
+}
+
+impl std::fmt::Display for GameResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return write!(f, "{}", match(self) {
+            GameResult::Ongoing => "ongoing",
+            GameResult::Checkmate => "checkmate",
+            GameResult::Stalemate => "stalemate",
+            GameResult::FiftyMoveRule => "draw by the fifty-move rule",
+            GameResult::ThreefoldRepetition => "draw by threefold repetition",
+            GameResult::FivefoldRepetition => "draw by fivefold repetition",
+            GameResult::SeventyFiveMoveRule => "draw by the seventy-five-move rule",
+            GameResult::InsufficientMaterial => "draw by insufficient material",
+        });
+    }
+}
+
+impl Position {
+    // Mate and stalemate come first since a mate delivered on the 100th ply still wins,
+    // then the automatic draws, then the draws a player has to claim
+    pub fn game_result(&self) -> GameResult {
+        if(self.generate_legal_moves().is_empty()) {
+            if(self.in_check()) {
+                return GameResult::Checkmate;
+            }
+            return GameResult::Stalemate;
+        }
+        let repetitions: usize = self.repetition_count();
+        if(repetitions >= 5) {
+            return GameResult::FivefoldRepetition;
+        }
+        if(self.state.halfmove_clock >= 150) {
+            return GameResult::SeventyFiveMoveRule;
+        }
+        if(self.has_insufficient_material()) {
+            return GameResult::InsufficientMaterial;
+        }
+        if(repetitions >= 3) {
+            return GameResult::ThreefoldRepetition;
+        }
+        if(self.state.halfmove_clock >= 100) {
+            return GameResult::FiftyMoveRule;
+        }
+        return GameResult::Ongoing;
+    }
+
+    // How many times the current position has occurred, itself included. Only positions since the last
+    // capture or pawn move can match, and only every other one has the same side to move.
+    pub fn repetition_count(&self) -> usize {
+        let mut count: usize = 1;
+        let reversible_plies: usize = (self.state.halfmove_clock as usize).min(self.history.len());
+        let mut distance: usize = 2;
+        while(distance <= reversible_plies) {
+            if(self.history[self.history.len() - distance].key == self.key) {
+                count += 1;
+            }
+            distance += 2;
+        }
+        return count;
+    }
+
+    // Neither side can mate: bare kings, a single minor piece, or bishops that all stand on one square color
+    pub fn has_insufficient_material(&self) -> bool {
+        let mut minor_pieces: usize = 0;
+        let mut bishop_square_colors: [bool; 2] = [false, false];
+        for (square, piece) in self.state.board.iter().enumerate() {
+            match(piece.data & 0b111) {
+                0b001 | 0b100 | 0b101 => return false,
+                0b010 => minor_pieces += 1,
+                0b011 => {
+                    minor_pieces += 1;
+                    bishop_square_colors[((square >> 3) + (square & 0b111)) & 1] = true;
+                }
+                _ => (),
+            }
+        }
+        if(minor_pieces <= 1) {
+            return true;
+        }
+        let has_knight: bool = self.state.board.iter().any(|piece| piece.data & 0b111 == 0b010);
+        let bishops_on_both_colors: bool = bishop_square_colors[0] && bishop_square_colors[1];
+        return !has_knight && !bishops_on_both_colors;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{fen::START_FEN, moves::Move};
+
+    fn play(fen: &str, moves: &str) -> Position {
+        let mut position: Position = Position::from(fen);
+        for uci in moves.split_whitespace() {
+            let chess_move: Move = Move::from_uci(uci, &position).unwrap();
+            position.make_move(chess_move).unwrap();
+        }
+        return position;
+    }
+
+    #[test]
+    fn detects_mate_and_stalemate() {
+        let fools_mate: Position = play(START_FEN, "f2f3 e7e5 g2g4 d8h4");
+        assert_eq!(fools_mate.game_result(), GameResult::Checkmate);
+        assert_eq!(fools_mate.game_result().score(fools_mate.state.color), "0-1");
+        assert_eq!(play("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1", "").game_result(), GameResult::Stalemate);
+        // Mate on the 100th ply still wins
+        assert_eq!(play("7k/8/6K1/8/8/8/8/R7 w - - 99 80", "a1a8").game_result(), GameResult::Checkmate);
+    }
+
+    #[test]
+    fn counts_repetitions() {
+        let knights: &str = "g1f3 g8f6 f3g1 f6g8";
+        let twice: Position = play(START_FEN, knights);
+        assert_eq!(twice.repetition_count(), 2);
+        assert_eq!(twice.game_result(), GameResult::Ongoing);
+        let three_times: Position = play(START_FEN, &[knights; 2].join(" "));
+        assert_eq!(three_times.repetition_count(), 3);
+        assert_eq!(three_times.game_result(), GameResult::ThreefoldRepetition);
+        let five_times: Position = play(START_FEN, &[knights; 4].join(" "));
+        assert_eq!(five_times.repetition_count(), 5);
+        assert_eq!(five_times.game_result(), GameResult::FivefoldRepetition);
+        // A pawn move in between makes the earlier positions unreachable
+        let reset: Position = play(START_FEN, &format!("{} e2e3 e7e6 {}", knights, knights));
+        assert_eq!(reset.repetition_count(), 2);
+        // Nothing can take en passant after 1. e4 e5, so the double pushes don't tell the positions apart
+        let double_pushes: Position = play(START_FEN, &format!("e2e4 e7e5 {}", knights));
+        assert_eq!(double_pushes.repetition_count(), 2);
+    }
+
+    #[test]
+    fn applies_move_rules() {
+        assert_eq!(play("4k3/8/8/8/8/8/8/R3K3 w - - 98 80", "a1a2").game_result(), GameResult::Ongoing);
+        assert_eq!(play("4k3/8/8/8/8/8/8/R3K3 w - - 99 80", "a1a2").game_result(), GameResult::FiftyMoveRule);
+        assert_eq!(play("4k3/8/8/8/8/8/8/R3K3 w - - 149 80", "a1a2").game_result(), GameResult::SeventyFiveMoveRule);
+        // A capture resets the count
+        let capture: Position = play("4k3/8/8/8/8/8/r7/R3K3 w - - 99 80", "a1a2");
+        assert_eq!(capture.state.halfmove_clock, 0);
+        assert_eq!(capture.game_result(), GameResult::Ongoing);
+    }
+
+    #[test]
+    fn detects_insufficient_material() {
+        let insufficient = |fen: &str| -> bool { Position::from(fen).has_insufficient_material() };
+        assert!(insufficient("8/8/4k3/8/8/4K3/8/8 w - - 0 1"));
+        assert!(insufficient("8/8/4k3/8/8/4K3/3B4/8 w - - 0 1"));
+        assert!(insufficient("8/8/4k3/8/8/4K3/3N4/8 w - - 0 1"));
+        // Bishops on d2 and a5 both stand on dark squares
+        assert!(insufficient("8/8/4k3/b7/8/4K3/3B4/8 w - - 0 1"));
+        assert!(!insufficient("8/8/4k3/8/b7/4K3/3B4/8 w - - 0 1"));
+        assert!(!insufficient("8/8/4k3/8/n7/4K3/3B4/8 w - - 0 1"));
+        assert!(!insufficient("8/8/4k3/8/8/4K3/3P4/8 w - - 0 1"));
+        assert!(!insufficient("8/8/4k3/8/8/4K3/3R4/8 w - - 0 1"));
+    }
+}
@@ -0,0 +1,206 @@
+use super::{error::PositionError, moves::Move, Position};
+
+const PIECE_LETTERS: [char; 7] = [' ', 'P', 'N', 'B', 'R', 'Q', 'K'];
+
+impl Move {
+    // Standard algebraic notation, e.g. Nbd7, exd5, e8=Q, O-O or Qh4#
+    pub fn to_san(&self, position: &Position) -> String {
+        if(self == &Move::NULL) {
+            return String::from("--");
+        }
+        let origin: u8 = self.origin();
+        let target: u8 = self.target();
+        let piece_type: u8 = position.state.board[origin as usize].data & 0b111;
+
+        let mut san: String = String::new();
+        if(self.is_castle()) {
+            san += match(self.flags()) {
+                super::moves::KING_CASTLE => "O-O",
+                _ => "O-O-O",
+            };
+        } else if(piece_type == 0b001) {
+            if(self.is_capture()) {
+                san.push((b'a' + (origin & 0b111)) as char);
+                san += "x";
+            }
+            san += &super::to_square(&target);
+            if(self.is_promotion()) {
+                san += &format!("={}", PIECE_LETTERS[self.promotion() as usize]);
+            }
+        } else {
+            san.push(PIECE_LETTERS[piece_type as usize]);
+            san += &disambiguation(position, *self, piece_type);
+            if(self.is_capture()) {
+                san += "x";
+            }
+            san += &super::to_square(&target);
+        }
+
+        let mut next_position: Position = position.clone();
+        if(next_position.make_move(*self).is_ok() && next_position.in_check()) {
+            san += match(next_position.generate_legal_moves().is_empty()) {
+                true => "#",
+                false => "+",
+            };
+        }
+        return san;
+    }
+
+    // Also takes 0-0 for castling, a missing or extra check suffix, annotations like ! or ?,
+    // lowercase or =-less promotions and long forms such as Ng1f3 or Ng1-f3
+    pub fn from_san(move_string: &str, position: &Position) -> Result<Move, PositionError> {
+        let illegal = |reason: String| -> PositionError {
+            return PositionError::IllegalMove {
+                mv: move_string.to_string(),
+                reason,
+            };
+        };
+        let trimmed: &str = move_string.trim_end_matches(['+', '#', '!', '?']);
+        let trimmed: &str = trimmed.strip_suffix("e.p.").unwrap_or(trimmed).trim_end();
+        let legal_moves: Vec<Move> = position.generate_legal_moves();
+
+        let castling_flags: Option<u8> = match(trimmed) {
+            "O-O" | "0-0" | "o-o" => Some(super::moves::KING_CASTLE),
+            "O-O-O" | "0-0-0" | "o-o-o" => Some(super::moves::QUEEN_CASTLE),
+            _ => None,
+        };
+        if let Some(flags) = castling_flags {
+            return match(legal_moves.into_iter().find(|legal_move| legal_move.flags() == flags)) {
+                Some(v) => Ok(v),
+                None => Err(illegal(String::from("castling isn't allowed here"))),
+            };
+        }
+
+        let mut characters: Vec<char> = trimmed.chars().filter(|&character| character != 'x' && character != ':' && character != '-').collect::<Vec<char>>();
+        let piece_type: u8 = match(characters.first()) {
+            Some(&letter) if "PNBRQK".contains(letter) => {
+                characters.remove(0);
+                PIECE_LETTERS.iter().position(|&piece_letter| piece_letter == letter).unwrap() as u8
+            }
+            _ => 0b001,
+        };
+
+        // A trailing letter after the target square is the promotion piece, with or without '='
+        let mut promotion: u8 = 0;
+        if let Some(&letter) = characters.last() {
+            if(characters.len() >= 3 && "nbrqNBRQ".contains(letter)) {
+                promotion = PIECE_LETTERS.iter().position(|&piece_letter| piece_letter == letter.to_ascii_uppercase()).unwrap() as u8;
+                characters.pop();
+                if(characters.last() == Some(&'=')) {
+                    characters.pop();
+                }
+            }
+        }
+
+        if(characters.len() < 2 || characters.len() > 4) {
+            return Err(illegal(String::from("expected a piece, an optional origin file or rank and a target square, e.g. Nbd7")));
+        }
+        let target_text: String = characters[characters.len() - 2..].iter().collect::<String>();
+        let target: u8 = super::moves::try_parse_square(&target_text)?;
+        let mut origin_file: Option<u8> = None;
+        let mut origin_rank: Option<u8> = None;
+        for &character in &characters[..characters.len() - 2] {
+            match(character) {
+                'a'..='h' if origin_file.is_none() && origin_rank.is_none() => origin_file = Some(character as u8 - b'a'),
+                '1'..='8' if origin_rank.is_none() => origin_rank = Some(character as u8 - b'1'),
+                _ => return Err(illegal(format!("'{}' can't disambiguate the origin square", character))),
+            }
+        }
+
+        let candidates: Vec<Move> = legal_moves.into_iter().filter(|legal_move| {
+            let origin: u8 = legal_move.origin();
+            return legal_move.target() == target
+                && !legal_move.is_castle()
+                && position.state.board[origin as usize].data & 0b111 == piece_type
+                && legal_move.promotion() == promotion
+                && origin_file.is_none_or(|file| origin & 0b111 == file)
+                && origin_rank.is_none_or(|rank| origin >> 3 == rank);
+        }).collect::<Vec<Move>>();
+        return match(candidates.len()) {
+            1 => Ok(candidates[0]),
+            0 if piece_type == 0b001 && promotion == 0 && (target >> 3 == 0 || target >> 3 == 7) => {
+                Err(illegal(String::from("a pawn reaching the last rank has to name its promotion piece")))
+            }
+            0 => Err(illegal(String::from("no legal move matches"))),
+            _ => Err(illegal(String::from("ambiguous, more than one legal move matches"))),
+        };
+    }
+}
+
+// The origin file when it tells the moves apart, else the rank, else both
+fn disambiguation(position: &Position, chess_move: Move, piece_type: u8) -> String {
+    let origin: u8 = chess_move.origin();
+    let rivals: Vec<u8> = position.generate_legal_moves()
+        .into_iter()
+        .filter(|other| {
+            other.target() == chess_move.target()
+                && other.origin() != origin
+                && position.state.board[other.origin() as usize].data & 0b111 == piece_type
+        })
+        .map(|other| other.origin())
+        .collect::<Vec<u8>>();
+    if(rivals.is_empty()) {
+        return String::new();
+    }
+    let square: String = super::to_square(&origin);
+    if(rivals.iter().all(|&rival| rival & 0b111 != origin & 0b111)) {
+        return square[0..1].to_string();
+    }
+    if(rivals.iter().all(|&rival| rival >> 3 != origin >> 3)) {
+        return square[1..2].to_string();
+    }
+    return square;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn san_of(fen: &str, uci: &str) -> String {
+        let position: Position = Position::from(fen);
+        return Move::from_uci(uci, &position).unwrap().to_san(&position);
+    }
+
+    fn uci_of(fen: &str, san: &str) -> String {
+        let position: Position = Position::from(fen);
+        return Move::from_san(san, &position).unwrap().to_string();
+    }
+
+    #[test]
+    fn formats_san() {
+        let kiwipete: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        assert_eq!(san_of(super::super::fen::START_FEN, "g1f3"), "Nf3");
+        assert_eq!(san_of(kiwipete, "e1g1"), "O-O");
+        assert_eq!(san_of(kiwipete, "e1c1"), "O-O-O");
+        assert_eq!(san_of(kiwipete, "d5e6"), "dxe6");
+        assert_eq!(san_of(kiwipete, "e5f7"), "Nxf7");
+        assert_eq!(san_of("4k3/8/8/8/8/8/R6R/4K3 w - - 0 1", "a2d2"), "Rad2");
+        assert_eq!(san_of("4k3/8/8/8/R7/8/8/R3K3 w - - 0 1", "a1a2"), "R1a2");
+        assert_eq!(san_of("8/7k/8/8/Q2Q4/8/8/Q3K3 w - - 0 1", "a4d1"), "Qa4d1");
+        assert_eq!(san_of("8/1P2k3/8/8/8/8/8/4K3 w - - 0 1", "b7b8q"), "b8=Q");
+        assert_eq!(san_of("4k3/R7/8/8/8/8/8/4K2R w - - 0 1", "h1h8"), "Rh8#");
+        assert_eq!(san_of("4k3/8/8/8/8/8/8/4K2R w - - 0 1", "h1h8"), "Rh8+");
+    }
+
+    #[test]
+    fn parses_san() {
+        let kiwipete: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        assert_eq!(uci_of(super::super::fen::START_FEN, "Nf3"), "g1f3");
+        assert_eq!(uci_of(super::super::fen::START_FEN, "e4"), "e2e4");
+        assert_eq!(uci_of(kiwipete, "0-0"), "e1g1");
+        assert_eq!(uci_of(kiwipete, "O-O-O+"), "e1c1");
+        assert_eq!(uci_of(kiwipete, "dxe6"), "d5e6");
+        assert_eq!(uci_of(kiwipete, "Qxf6!?"), "f3f6");
+        assert_eq!(uci_of("4k3/8/8/8/8/8/R6R/4K3 w - - 0 1", "Rad2"), "a2d2");
+        assert_eq!(uci_of("8/7k/8/8/Q2Q4/8/8/Q3K3 w - - 0 1", "Qa4-d1"), "a4d1");
+        assert_eq!(uci_of("8/1P2k3/8/8/8/8/8/4K3 w - - 0 1", "b8=q"), "b7b8q");
+        assert_eq!(uci_of("8/1P2k3/8/8/8/8/8/4K3 w - - 0 1", "b8N"), "b7b8n");
+        assert_eq!(uci_of("4k3/R7/8/8/8/8/8/4K2R w - - 0 1", "Rh8"), "h1h8");
+
+        let position: Position = Position::from("4k3/8/8/8/8/8/R6R/4K3 w - - 0 1");
+        assert!(Move::from_san("Rd2", &position).is_err());
+        assert!(Move::from_san("Nf3", &position).is_err());
+        assert!(Move::from_san("O-O-O", &Position::from("4k3/8/8/8/8/8/8/4K2R w K - 0 1")).is_err());
+        assert!(Move::from_san("b8", &Position::from("8/1P2k3/8/8/8/8/8/4K3 w - - 0 1")).is_err());
+    }
+}
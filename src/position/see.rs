@@ -0,0 +1,186 @@
+use super::bitboard::{self, Bitboard, Bitboards};
+use super::{moves::Move, Position};
+
+// Exchange values indexed by piece type, the king is worth more than everything else put together
+pub const SEE_VALUES: [i32; 7] = [0, 100, 300, 300, 500, 900, 20000];
+
+// Material the side to move wins (or loses when negative) on the target square if both sides keep
+// recapturing with their least valuable attacker and either may stop when going on would lose more.
+// Sliders lined up behind a capturer join in as soon as the square in front of them empties.
+pub fn see(position: &Position, chess_move: Move) -> i32 {
+    if(chess_move.is_castle()) {
+        return 0;
+    }
+    let bitboards: &Bitboards = &position.bitboards;
+    let origin: u8 = chess_move.origin();
+    let target: u8 = chess_move.target();
+
+    let mut occupied: Bitboard = bitboards.occupied() ^ (1 << origin);
+    let mut gains: [i32; 32] = [0; 32];
+    let mut piece_value: i32 = SEE_VALUES[(position.state.board[origin as usize].data & 0b111) as usize];
+    if(chess_move.is_en_passant()) {
+        occupied ^= 1 << ((origin & 0b111000) | (target & 0b111));
+        gains[0] = SEE_VALUES[0b001];
+    } else {
+        gains[0] = SEE_VALUES[(position.state.board[target as usize].data & 0b111) as usize];
+    }
+    if(chess_move.is_promotion()) {
+        piece_value = SEE_VALUES[chess_move.promotion() as usize];
+        gains[0] += piece_value - SEE_VALUES[0b001];
+    }
+
+    let rooks_and_queens: Bitboard = bitboards.pieces[0b100] | bitboards.pieces[0b101];
+    let bishops_and_queens: Bitboard = bitboards.pieces[0b011] | bitboards.pieces[0b101];
+    let mut attackers: Bitboard = bitboards.attackers(target, false, occupied) | bitboards.attackers(target, true, occupied);
+    let mut side: bool = !position.state.color;
+    let mut depth: usize = 0;
+    loop {
+        depth += 1;
+        // What the side to recapture wins if it takes now, before knowing whether it can
+        gains[depth] = piece_value - gains[depth - 1];
+        if(depth == gains.len() - 1) {
+            break;
+        }
+        let own_attackers: Bitboard = attackers & bitboards.colors[side as usize];
+        if(own_attackers == 0) {
+            break;
+        }
+        let piece_type: usize = (0b001..=0b110).find(|&piece_type| own_attackers & bitboards.pieces[piece_type] != 0).unwrap();
+        // The king can only take last, when nothing defends the square anymore
+        if(piece_type == 0b110 && attackers & bitboards.colors[!side as usize] != 0) {
+            break;
+        }
+        let square: u8 = bitboard::lsb(own_attackers & bitboards.pieces[piece_type]);
+        occupied ^= 1 << square;
+        attackers |= (bitboard::rook_attacks(target, occupied) & rooks_and_queens)
+            | (bitboard::bishop_attacks(target, occupied) & bishops_and_queens);
+        attackers &= occupied;
+        piece_value = SEE_VALUES[piece_type];
+        side = !side;
+    }
+    // The last speculative gain had no capture behind it, unwind the rest keeping each side's better choice
+    while(depth > 1) {
+        depth -= 1;
+        gains[depth - 1] = -(-gains[depth - 1]).max(gains[depth]);
+    }
+    return gains[0];
+}
+
+impl Position {
+    // Whether the exchange started by the move wins at least threshold, same result as see(..) >= threshold.
+    // Instead of building the whole swap list it keeps the balance relative to the threshold and stops as soon
+    // as the side to recapture can no longer change the answer.
+    pub fn see_ge(&self, chess_move: Move, threshold: i32) -> bool {
+        if(chess_move.is_castle()) {
+            return 0 >= threshold;
+        }
+        let bitboards: &Bitboards = &self.bitboards;
+        let origin: u8 = chess_move.origin();
+        let target: u8 = chess_move.target();
+
+        let mut occupied: Bitboard = bitboards.occupied() ^ (1 << origin);
+        let mut captured_value: i32 = SEE_VALUES[(self.state.board[target as usize].data & 0b111) as usize];
+        let mut piece_value: i32 = SEE_VALUES[(self.state.board[origin as usize].data & 0b111) as usize];
+        if(chess_move.is_en_passant()) {
+            occupied ^= 1 << ((origin & 0b111000) | (target & 0b111));
+            captured_value = SEE_VALUES[0b001];
+        }
+        if(chess_move.is_promotion()) {
+            piece_value = SEE_VALUES[chess_move.promotion() as usize];
+            captured_value += piece_value - SEE_VALUES[0b001];
+        }
+
+        // What we're up after our capture if nothing takes back, less the threshold
+        let mut swap: i32 = captured_value - threshold;
+        if(swap < 0) {
+            return false;
+        }
+        // Still enough when our piece is lost for nothing
+        swap = piece_value - swap;
+        if(swap <= 0) {
+            return true;
+        }
+
+        let rooks_and_queens: Bitboard = bitboards.pieces[0b100] | bitboards.pieces[0b101];
+        let bishops_and_queens: Bitboard = bitboards.pieces[0b011] | bitboards.pieces[0b101];
+        let mut attackers: Bitboard = bitboards.attackers(target, false, occupied) | bitboards.attackers(target, true, occupied);
+        let mut side: bool = self.state.color;
+        let mut result: i32 = 1; // Whether the threshold is reached if the exchange stopped here
+        loop {
+            side = !side;
+            attackers &= occupied;
+            let own_attackers: Bitboard = attackers & bitboards.colors[side as usize];
+            if(own_attackers == 0) {
+                break;
+            }
+            result ^= 1;
+            let piece_type: usize = (0b001..=0b110).find(|&piece_type| own_attackers & bitboards.pieces[piece_type] != 0).unwrap();
+            // The king can only take last, when nothing defends the square anymore
+            if(piece_type == 0b110) {
+                return match(attackers & bitboards.colors[!side as usize] != 0) {
+                    true => result == 0,
+                    false => result == 1,
+                };
+            }
+            // The capturing piece is the next one at stake, once it isn't worth enough the side can stop
+            swap = SEE_VALUES[piece_type] - swap;
+            if(swap < result) {
+                break;
+            }
+            occupied ^= 1 << bitboard::lsb(own_attackers & bitboards.pieces[piece_type]);
+            attackers |= (bitboard::rook_attacks(target, occupied) & rooks_and_queens)
+                | (bitboard::bishop_attacks(target, occupied) & bishops_and_queens);
+        }
+        return result == 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn see_of(fen: &str, uci: &str) -> i32 {
+        let position: Position = Position::from(fen);
+        return see(&position, Move::from_uci(uci, &position).unwrap());
+    }
+
+    #[test]
+    fn swaps_off_exchanges() {
+        // Undefended pawn
+        assert_eq!(see_of("1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1", "e1e5"), 100);
+        // Pawn defended by a knight, a bishop and a queen hidden behind it, white comes out behind
+        assert_eq!(see_of("1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1", "d3e5"), -200);
+        // The e1 queen backs up the e2 rook through it, white wins the pawn for nothing
+        assert_eq!(see_of("4k3/4r3/8/4p3/8/8/4R3/4Q1K1 w - - 0 1", "e2e5"), 100);
+        // Defended twice, attacked by a rook with a queen behind it: the rook is lost for a pawn
+        assert_eq!(see_of("4k3/4r3/3p4/4p3/8/8/4R3/4Q1K1 w - - 0 1", "e2e5"), -400);
+        // The knight is lost for a pawn once g3 takes back, the exchange doesn't stop when both choices look bad
+        assert_eq!(see_of("1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1", "d3f4"), -200);
+        // Quiet move onto an attacked square
+        assert_eq!(see_of("4k3/8/3p4/8/4N3/8/8/4K3 w - - 0 1", "e4c5"), -300);
+        // En passant and promotion
+        assert_eq!(see_of("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "e5d6"), 100);
+        assert_eq!(see_of("4k3/P7/8/8/8/8/8/4K3 w - - 0 1", "a7a8q"), 800);
+    }
+
+    #[test]
+    fn agrees_with_threshold_test() {
+        let fens: [&str; 6] = [
+            "1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1",
+            "4k3/4r3/3p4/4p3/8/8/4R3/4Q1K1 w - - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1",
+            "3r2k1/1b3ppp/8/3pN3/1n1P4/2PQ1B2/5PPP/3R2K1 w - - 0 1",
+            "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1",
+        ];
+        for fen in fens {
+            let position: Position = Position::from(fen);
+            for chess_move in position.generate_legal_moves() {
+                let value: i32 = see(&position, chess_move);
+                for threshold in (-1000..=1000).step_by(50).chain([value - 1, value, value + 1]) {
+                    assert_eq!(position.see_ge(chess_move, threshold), value >= threshold, "{} {} {}", fen, chess_move, threshold);
+                }
+            }
+        }
+    }
+}
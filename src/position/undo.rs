@@ -0,0 +1,13 @@
+use super::moves::Move;
+
+// Everything make_move throws away that unmake_move can't recompute from the move itself
+#[derive(Copy, Clone, Debug)]
+pub struct UndoRecord {
+    pub chess_move: Move,
+    pub moved_piece: u8, // Piece.data of the moving piece before the move, keeps the hasMoved flag and the pawn of a promotion
+    pub captured_piece: u8, // Piece.data of the captured piece, 0 when nothing was captured
+    pub castle: u8,
+    pub en_passant: u8,
+    pub halfmove_clock: u8,
+    pub key: u64,
+}
@@ -0,0 +1,177 @@
+use super::bitboard::{Squares, PAWN_ATTACKS};
+use super::piece::Piece;
+
+pub struct ZobristKeys {
+    pub pieces: [[u64; 64]; 12], // Indexed by color * 6 + type - 1, see Piece.data
+    pub side: u64, // Toggled when BLACK is to move
+    pub castle: [u64; 16], // Indexed by the whole ParsedFEN.castle nibble
+    pub en_passant: [u64; 8], // Indexed by the file of the en passant square
+}
+
+// splitmix64, good enough to fill the tables and usable in a const fn
+const fn next_random(state: u64) -> (u64, u64) {
+    let state: u64 = state.wrapping_add(0x9E3779B97F4A7C15);
+    let mut z: u64 = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    return (state, z ^ (z >> 31));
+}
+
+const fn generate_keys() -> ZobristKeys {
+    let mut keys: ZobristKeys = ZobristKeys {
+        pieces: [[0; 64]; 12],
+        side: 0,
+        castle: [0; 16],
+        en_passant: [0; 8],
+    };
+    let mut state: u64 = 0x54534D4368657373; // "TSMChess"
+    let mut random: u64;
+
+    let mut piece_index: usize = 0;
+    while(piece_index < 12) {
+        let mut square: usize = 0;
+        while(square < 64) {
+            (state, random) = next_random(state);
+            keys.pieces[piece_index][square] = random;
+            square += 1;
+        }
+        piece_index += 1;
+    }
+
+    (state, random) = next_random(state);
+    keys.side = random;
+
+    // Each right gets its own key and a combination is the xor of its rights, so clearing one right is a single xor
+    let mut rights: [u64; 4] = [0; 4];
+    let mut i: usize = 0;
+    while(i < 4) {
+        (state, random) = next_random(state);
+        rights[i] = random;
+        i += 1;
+    }
+    let mut castle: usize = 0;
+    while(castle < 16) {
+        let mut bit: usize = 0;
+        while(bit < 4) {
+            if(castle & (1 << bit) != 0) {
+                keys.castle[castle] ^= rights[bit];
+            }
+            bit += 1;
+        }
+        castle += 1;
+    }
+
+    let mut file: usize = 0;
+    while(file < 8) {
+        (state, random) = next_random(state);
+        keys.en_passant[file] = random;
+        file += 1;
+    }
+    return keys;
+}
+
+pub static KEYS: ZobristKeys = generate_keys();
+
+pub fn piece_key(piece_data: u8, square: u8) -> u64 {
+    if(piece_data & 0b111 == 0) {
+        return 0;
+    }
+    let color: usize = ((piece_data & 0b1000) >> 3) as usize;
+    let piece_type: usize = (piece_data & 0b111) as usize;
+    return KEYS.pieces[color * 6 + piece_type - 1][square as usize];
+}
+
+// The en passant square only counts when a pawn of the side to move (color) stands next to the pushed pawn,
+// otherwise the same position reached without a double push would get another key
+pub fn en_passant_key(board: &[Piece; 64], en_passant: u8, color: bool) -> u64 {
+    if(en_passant >= 64) {
+        return 0;
+    }
+    let capturing_pawn: u8 = 0b001 | ((color as u8) << 3);
+    let can_capture: bool = Squares(PAWN_ATTACKS[!color as usize][en_passant as usize])
+        .any(|square| board[square as usize].data & 0b1111 == capturing_pawn);
+    if(!can_capture) {
+        return 0;
+    }
+    return KEYS.en_passant[(en_passant & 0b111) as usize];
+}
+
+// Full recompute, make_move keeps the key up to date incrementally and checks itself against this in debug builds
+pub fn compute_key(board: &[Piece; 64], color: bool, castle: u8, en_passant: u8) -> u64 {
+    let mut key: u64 = 0;
+    for (square, piece) in board.iter().enumerate() {
+        key ^= piece_key(piece.data, square as u8);
+    }
+    if(color) {
+        key ^= KEYS.side;
+    }
+    key ^= KEYS.castle[(castle & 0b1111) as usize];
+    key ^= en_passant_key(board, en_passant, color);
+    return key;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{moves::Move, Position};
+
+    fn play(position: &mut Position, moves: &str) {
+        for uci in moves.split_whitespace() {
+            position.make_move(Move::from_uci(uci, position).unwrap()).unwrap();
+        }
+    }
+
+    #[test]
+    fn hashes_en_passant_only_when_it_can_be_taken() {
+        let mut lone_push: Position = Position::from("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1");
+        play(&mut lone_push, "e2e4");
+        assert_eq!(lone_push.key, Position::from("4k3/8/8/8/4P3/8/8/4K3 b - - 0 1").key);
+        // Here the d4 pawn can take on e3, so the position with the en passant right is another one
+        let mut pushed: Position = Position::from("4k3/8/8/8/3p4/8/4P3/4K3 w - - 0 1");
+        play(&mut pushed, "e2e4");
+        assert_eq!(pushed.key, Position::from("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1").key);
+        assert_ne!(pushed.key, Position::from("4k3/8/8/8/3pP3/8/8/4K3 b - - 0 1").key);
+        // A pawn of the side that just pushed doesn't count
+        assert_eq!(
+            Position::from("4k3/8/8/8/4PP2/8/8/4K3 b - e3 0 1").key,
+            Position::from("4k3/8/8/8/4PP2/8/8/4K3 b - - 0 1").key
+        );
+    }
+
+    // Plays the moves one at a time, with "null" for a null move, and checks the incremental key against a full
+    // recompute after every move and again while taking them all back
+    fn check_keys(fen: &str, moves: &str) {
+        let mut position: Position = Position::from(fen);
+        let mut keys: Vec<u64> = vec![position.key];
+        for uci in moves.split_whitespace() {
+            match(uci) {
+                "null" => position.make_null_move(),
+                _ => play(&mut position, uci),
+            }
+            assert_eq!(position.key, position.compute_key(), "after {} in {}", uci, fen);
+            keys.push(position.key);
+        }
+        for uci in moves.split_whitespace().rev() {
+            assert_eq!(keys.pop(), Some(position.key));
+            match(uci) {
+                "null" => position.unmake_null_move(),
+                _ => position.unmake_move().unwrap(),
+            }
+            assert_eq!(position.key, position.compute_key(), "taking back {} in {}", uci, fen);
+        }
+        assert_eq!(keys, vec![Position::from(fen).key]);
+    }
+
+    #[test]
+    fn updates_keys_incrementally() {
+        // Castling on both sides, and castling rights lost by king and rook moves and by a rook capture
+        check_keys("r3k2r/ppp1pppp/8/8/8/8/PPP1PPPP/R3K2R w KQkq - 0 1", "e1g1 e8c8 f1e1 d8d1 e1d1");
+        check_keys("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "a1a8 e8f7 h1h7 f7g6 e1d1");
+        // Promotions, with and without a capture, and underpromotions
+        check_keys("1n6/P7/8/k7/8/8/6p1/4K2R w K - 0 1", "a7b8q g2h1n e1d2 a5a4");
+        check_keys("1n6/P7/8/k7/8/8/6p1/4K2R w K - 0 1", "a7a8n g2h1q e1e2 h1h8 a8c7 h8c8");
+        // En passant taken, en passant left for a null move, and a double push nothing can take
+        check_keys("4k3/3p4/8/4P3/8/8/2P5/4K3 b - - 0 1", "d7d5 e5d6 e8d7 c2c4 null c4c5 d7c6");
+        check_keys("4k3/3p4/8/4P3/8/8/8/4K3 b - - 0 1", "d7d5 null e8d7 null null");
+        check_keys("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3", "f1c4 g8f6 null d7d5 e4d5 f6d5 e1g1");
+    }
+}
@@ -0,0 +1,235 @@
+use super::super::position::{moves::Move, undo::UndoRecord, Position};
+use super::MAX_PLY;
+
+// History scores stay within -MAX_HISTORY..=MAX_HISTORY, see apply_bonus
+pub const MAX_HISTORY: i32 = 16384;
+
+// How many earlier moves the continuation history looks at, 1 = the opponent's last move, 2 = our own before it
+const CONTINUATION_PLIES: usize = 2;
+
+// One table of [piece][target] scores per earlier move, the piece being Piece.data & 0b1111
+type PieceToTable = [[i16; 64]; 16];
+
+// What the search learned about moves so far, used to order them and to decide how much to reduce them
+pub struct History {
+    killers: Vec<[Move; 2]>, // Two quiet moves per ply that caused a beta cutoff, the newest first
+    butterfly: Box<[[[i16; 64]; 64]; 2]>, // Indexed by color, origin and target
+    countermoves: [[Move; 64]; 16], // The quiet reply that refuted a move, indexed by its piece and target
+    continuation: Vec<PieceToTable>, // Indexed by an earlier move's piece * 64 + target, then the quiet move's piece and target
+    captures: Box<[[[i16; 7]; 64]; 16]>, // Indexed by the capturing piece, the target and the captured piece type
+}
+
+impl History {
+    pub fn new() -> History {
+        return History {
+            killers: vec![[Move::NULL; 2]; MAX_PLY + 1],
+            butterfly: Box::new([[[0; 64]; 64]; 2]),
+            countermoves: [[Move::NULL; 64]; 16],
+            continuation: vec![[[0; 64]; 16]; 16 * 64],
+            captures: Box::new([[[0; 7]; 64]; 16]),
+        };
+    }
+
+    pub fn killers(&self, ply: usize) -> [Move; 2] {
+        return self.killers[ply];
+    }
+
+    // The move that refuted the last move played, NULL when there's none or the last move was a null move
+    pub fn countermove(&self, position: &Position) -> Move {
+        return match(previous_move(position, 1)) {
+            Some(context) => self.countermoves[context / 64][context % 64],
+            None => Move::NULL,
+        };
+    }
+
+    // Butterfly history plus the continuation history of the moves one and two plies back
+    pub fn quiet_score(&self, position: &Position, chess_move: Move) -> i32 {
+        let (piece, target): (usize, usize) = moving_piece(position, chess_move);
+        let mut score: i32 = self.butterfly[position.state.color as usize][chess_move.origin() as usize][target] as i32;
+        for distance in 1..=CONTINUATION_PLIES {
+            if let Some(context) = previous_move(position, distance) {
+                score += self.continuation[context][piece][target] as i32;
+            }
+        }
+        return score;
+    }
+
+    pub fn capture_score(&self, position: &Position, chess_move: Move) -> i32 {
+        let (piece, target): (usize, usize) = moving_piece(position, chess_move);
+        return self.captures[piece][target][captured_type(position, chess_move)] as i32;
+    }
+
+    // A move caused a beta cutoff. A quiet one becomes a killer and a countermove and is rewarded while
+    // the quiet moves searched before it are penalized. The captures searched before it are penalized either way.
+    pub fn update(&mut self, position: &Position, ply: usize, best_move: Move, quiets_tried: &[Move], captures_tried: &[Move], depth: u32) {
+        let bonus: i32 = history_bonus(depth);
+        if(is_quiet(best_move)) {
+            if(self.killers[ply][0] != best_move) {
+                self.killers[ply][1] = self.killers[ply][0];
+                self.killers[ply][0] = best_move;
+            }
+            if let Some(context) = previous_move(position, 1) {
+                self.countermoves[context / 64][context % 64] = best_move;
+            }
+            self.update_quiet(position, best_move, bonus);
+            for &quiet in quiets_tried {
+                self.update_quiet(position, quiet, -bonus);
+            }
+        } else {
+            self.update_capture(position, best_move, bonus);
+        }
+        for &capture in captures_tried {
+            self.update_capture(position, capture, -bonus);
+        }
+    }
+
+    fn update_quiet(&mut self, position: &Position, chess_move: Move, bonus: i32) {
+        let (piece, target): (usize, usize) = moving_piece(position, chess_move);
+        apply_bonus(&mut self.butterfly[position.state.color as usize][chess_move.origin() as usize][target], bonus);
+        for distance in 1..=CONTINUATION_PLIES {
+            if let Some(context) = previous_move(position, distance) {
+                apply_bonus(&mut self.continuation[context][piece][target], bonus);
+            }
+        }
+    }
+
+    fn update_capture(&mut self, position: &Position, chess_move: Move, bonus: i32) {
+        let (piece, target): (usize, usize) = moving_piece(position, chess_move);
+        apply_bonus(&mut self.captures[piece][target][captured_type(position, chess_move)], bonus);
+    }
+}
+
+impl Default for History {
+    fn default() -> History {
+        return History::new();
+    }
+}
+
+pub fn history_bonus(depth: u32) -> i32 {
+    return (16 * depth * depth).min(1600) as i32;
+}
+
+// Gravity update: the closer the entry already is to the bound in the bonus' direction, the less it moves,
+// so entries saturate smoothly and old results fade as new ones come in
+pub fn apply_bonus(entry: &mut i16, bonus: i32) {
+    let value: i32 = *entry as i32;
+    *entry = (value + bonus - value * bonus.abs() / MAX_HISTORY) as i16;
+}
+
+pub fn is_quiet(chess_move: Move) -> bool {
+    return !chess_move.is_capture() && !chess_move.is_promotion();
+}
+
+// Piece (Piece.data & 0b1111) and target square of a move about to be played
+fn moving_piece(position: &Position, chess_move: Move) -> (usize, usize) {
+    let piece: usize = (position.state.board[chess_move.origin() as usize].data & 0b1111) as usize;
+    return (piece, chess_move.target() as usize);
+}
+
+// Type of the piece a move takes, 0 for a promotion that doesn't capture
+fn captured_type(position: &Position, chess_move: Move) -> usize {
+    if(chess_move.is_en_passant()) {
+        return 0b001;
+    }
+    return (position.state.board[chess_move.target() as usize].data & 0b111) as usize;
+}
+
+// piece * 64 + target of the move played distance plies ago, None past the start of the game or a null move
+fn previous_move(position: &Position, distance: usize) -> Option<usize> {
+    let record: &UndoRecord = position.history.get(position.history.len().checked_sub(distance)?)?;
+    if(record.chess_move == Move::NULL) {
+        return None;
+    }
+    return Some((record.moved_piece & 0b1111) as usize * 64 + record.chess_move.target() as usize);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saturates_history() {
+        let mut entry: i16 = 0;
+        for _ in 0..1000 {
+            apply_bonus(&mut entry, history_bonus(40));
+            assert!(entry as i32 <= MAX_HISTORY);
+        }
+        assert!(entry as i32 > MAX_HISTORY * 9 / 10);
+        for _ in 0..1000 {
+            apply_bonus(&mut entry, -history_bonus(40));
+            assert!(entry as i32 >= -MAX_HISTORY);
+        }
+        assert!((entry as i32) < -MAX_HISTORY * 9 / 10);
+        // Near the bound a bonus in the same direction barely moves the entry, one the other way moves it a lot
+        let saturated: i16 = entry;
+        apply_bonus(&mut entry, -history_bonus(40));
+        assert!((entry - saturated).abs() <= 1);
+        apply_bonus(&mut entry, history_bonus(40));
+        assert!(entry as i32 - saturated as i32 > history_bonus(40));
+    }
+
+    fn play(moves: &str) -> Position {
+        let mut position: Position = Position::new();
+        for uci in moves.split_whitespace() {
+            position.make_move(Move::from_uci(uci, &position).unwrap()).unwrap();
+        }
+        return position;
+    }
+
+    #[test]
+    fn scores_continuations() {
+        let mut history: History = History::new();
+        let position: Position = play("e2e4 e7e5 g1f3");
+        let knight: Move = Move::from_uci("b8c6", &position).unwrap();
+        let bonus: i32 = history_bonus(4);
+        history.update(&position, 0, knight, &[], &[], 4);
+        // Butterfly plus the continuations of Nf3 and of our own e5
+        assert_eq!(history.quiet_score(&position, knight), 3 * bonus);
+        // The countermove is keyed by the piece and target of Nf3
+        assert_eq!(history.countermove(&position), knight);
+
+        // After Nc3 instead of Nf3 only the continuation of e5 still applies
+        let other_reply: Position = play("e2e4 e7e5 b1c3");
+        assert_eq!(history.quiet_score(&other_reply, Move::from_uci("b8c6", &other_reply).unwrap()), 2 * bonus);
+        assert_eq!(history.countermove(&other_reply), Move::NULL);
+        // Another piece going to the same square has its own entries
+        assert_eq!(history.quiet_score(&position, Move::from_uci("c7c6", &position).unwrap()), 0);
+
+        // Moves searched before the cutoff are penalized in every table
+        let pawn: Move = Move::from_uci("d7d6", &position).unwrap();
+        history.update(&position, 0, knight, &[pawn], &[], 4);
+        assert_eq!(history.quiet_score(&position, pawn), -3 * bonus);
+
+        for _ in 0..1000 {
+            history.update(&position, 0, knight, &[pawn], &[], 40);
+        }
+        assert!(history.quiet_score(&position, knight) <= 3 * MAX_HISTORY);
+        assert!(history.quiet_score(&position, knight) > 3 * MAX_HISTORY * 9 / 10);
+        assert!(history.quiet_score(&position, pawn) >= -3 * MAX_HISTORY);
+    }
+
+    #[test]
+    fn scores_captures() {
+        let mut history: History = History::new();
+        let position: Position = Position::from("4k3/8/8/3p4/8/2N2B2/8/4K3 w - - 0 1");
+        let knight_takes: Move = Move::from_uci("c3d5", &position).unwrap();
+        let bishop_takes: Move = Move::from_uci("f3d5", &position).unwrap();
+        let bonus: i32 = history_bonus(4);
+        history.update(&position, 0, knight_takes, &[], &[bishop_takes], 4);
+        assert_eq!(history.capture_score(&position, knight_takes), bonus);
+        assert_eq!(history.capture_score(&position, bishop_takes), -bonus);
+        // A capture never becomes a killer or a countermove
+        assert_eq!(history.killers(0), [Move::NULL; 2]);
+
+        // The same piece taking something else on the same square has its own entry
+        let queen_on_d5: Position = Position::from("4k3/8/8/3q4/8/2N2B2/8/4K3 w - - 0 1");
+        assert_eq!(history.capture_score(&queen_on_d5, Move::from_uci("c3d5", &queen_on_d5).unwrap()), 0);
+
+        for _ in 0..1000 {
+            history.update(&position, 0, knight_takes, &[], &[bishop_takes], 40);
+        }
+        assert!(history.capture_score(&position, knight_takes) <= MAX_HISTORY);
+        assert!(history.capture_score(&position, bishop_takes) >= -MAX_HISTORY);
+        assert!(history.capture_score(&position, knight_takes) > MAX_HISTORY * 9 / 10);
+    }
+}
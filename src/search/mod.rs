@@ -0,0 +1,637 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+pub mod history;
+pub mod ordering;
+pub mod picker;
+pub mod pruning;
+pub mod time;
+pub mod tt;
+
+use self::history::History;
+use self::picker::MovePicker;
+use self::pruning::Pruning;
+use self::time::TimeManager;
+use self::tt::TranspositionTable;
+use super::eval;
+use super::position::{bitboard::{Bitboard, Bitboards}, moves::Move, Position};
+use super::uci::{GoParams, Options};
+
+pub const MATE: i32 = 32000;
+pub const INFINITY: i32 = 32001;
+pub const MAX_PLY: usize = 128;
+
+// Scores above this are mates, the distance to mate is MATE - |score| plies
+pub const MATE_BOUND: i32 = MATE - MAX_PLY as i32;
+
+// Half width of the first aspiration window in centipawns, and the first depth that uses one
+const ASPIRATION_WINDOW: i32 = 25;
+const ASPIRATION_DEPTH: u32 = 5;
+
+// Flags shared between the UCI loop and the search thread
+pub struct Signals {
+    pub stop: AtomicBool,
+    pub ponder: AtomicBool, // Set while "go ponder" hasn't been answered by "ponderhit" or "stop"
+}
+
+impl Signals {
+    pub fn new() -> Signals {
+        return Signals {
+            stop: AtomicBool::new(false),
+            ponder: AtomicBool::new(false),
+        };
+    }
+}
+
+impl Default for Signals {
+    fn default() -> Signals {
+        return Signals::new();
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Limits {
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    pub mate: Option<u32>, // Moves, from "go mate"
+    pub infinite: bool,
+    pub searchmoves: Vec<String>,
+}
+
+impl Limits {
+    pub fn from(params: &GoParams) -> Limits {
+        return Limits {
+            // "go mate N" looks for a mate in N moves, which is 2N - 1 plies deep
+            depth: params.depth.or(params.mate.map(|moves| (moves * 2).saturating_sub(1).max(1))),
+            nodes: params.nodes,
+            mate: params.mate,
+            infinite: params.infinite,
+            searchmoves: params.searchmoves.clone(),
+        };
+    }
+}
+
+pub struct Search<'a> {
+    pub position: Position,
+    pub limits: Limits,
+    tt: &'a mut TranspositionTable,
+    signals: Arc<Signals>,
+    time: TimeManager,
+    pruning: Pruning,
+    history: History,
+    pondering: bool,
+    pub nodes: u64,
+    stopped: bool,
+    pv_table: Vec<Vec<Move>>, // pv_table[ply] holds the principal variation starting at that ply
+}
+
+impl<'a> Search<'a> {
+    pub fn new(position: Position, limits: Limits, time: TimeManager, pruning: Pruning, tt: &'a mut TranspositionTable, signals: Arc<Signals>) -> Search<'a> {
+        return Search {
+            position,
+            limits,
+            tt,
+            pruning,
+            history: History::new(),
+            pondering: signals.ponder.load(Ordering::SeqCst),
+            signals,
+            time,
+            nodes: 0,
+            stopped: false,
+            pv_table: vec![Vec::new(); MAX_PLY + 1],
+        };
+    }
+
+    // Iterative deepening, returns the best move and the principal variation of the last completed iteration
+    pub fn run(&mut self) -> (Move, Vec<Move>) {
+        let mut root_moves: Vec<Move> = self.position.generate_legal_moves();
+        if(!self.limits.searchmoves.is_empty()) {
+            root_moves.retain(|root_move| self.limits.searchmoves.contains(&root_move.to_string()));
+        }
+        if(root_moves.is_empty()) {
+            return (Move::NULL, Vec::new());
+        }
+        ordering::order_moves(&self.position, &mut root_moves, Move::NULL);
+
+        let mut best_move: Move = root_moves[0];
+        let mut best_pv: Vec<Move> = vec![best_move];
+        let max_depth: u32 = self.limits.depth.unwrap_or(MAX_PLY as u32 - 1).min(MAX_PLY as u32 - 1);
+        let mut stability: usize = 0;
+
+        let mut score: i32 = 0;
+        for depth in 1..=max_depth {
+            score = self.aspiration_search(&mut root_moves, depth, score, &best_pv);
+            if(self.stopped) {
+                break;
+            }
+            if(root_moves[0] == best_move) {
+                stability += 1;
+            } else {
+                stability = 0;
+            }
+            best_move = root_moves[0];
+            best_pv = self.pv_table[0].clone();
+            self.print_info(depth, score, tt::BOUND_EXACT, &best_pv);
+
+            self.update_pondering();
+            if(!self.limits.infinite && !self.pondering && self.time.soft_limit_reached(stability)) {
+                break;
+            }
+
+            if(!self.limits.infinite && self.mate_is_proven(score, depth)) {
+                break;
+            }
+        }
+        if(best_pv.len() == 1) {
+            // A transposition table cutoff right after the root leaves no reply to ponder on, look it up instead
+            if let Some(ponder_move) = self.tt_reply(best_move) {
+                best_pv.push(ponder_move);
+            }
+        }
+        return (best_move, best_pv);
+    }
+
+    // Null-move pruning and late move reductions can hide a shorter mate or a refutation of a longer one, so a
+    // mate is only final once every line up to it was searched at full width, or when it's the one "go mate" asked for
+    fn mate_is_proven(&self, score: i32, depth: u32) -> bool {
+        if(score.abs() < MATE_BOUND) {
+            return false;
+        }
+        let distance: i32 = MATE - score.abs();
+        if(self.limits.mate.is_some_and(|moves| score > 0 && distance < 2 * moves as i32)) {
+            return true;
+        }
+        return distance <= depth as i32;
+    }
+
+    fn tt_reply(&mut self, chess_move: Move) -> Option<Move> {
+        self.position.make_move(chess_move).ok()?;
+        let reply: Option<Move> = self.tt.probe(self.position.key)
+            .map(|entry| entry.best_move())
+            .filter(|reply| self.position.generate_legal_moves().contains(reply));
+        self.position.undo_move().expect("a move was just made");
+        return reply;
+    }
+
+    // Searches a narrow window around the previous iteration's score, widening it on the failing side until
+    // the score falls inside. Shallow iterations are too unstable to guess from and use the full window.
+    fn aspiration_search(&mut self, root_moves: &mut [Move], depth: u32, previous_score: i32, previous_pv: &[Move]) -> i32 {
+        let mut delta: i32 = ASPIRATION_WINDOW;
+        let (mut alpha, mut beta): (i32, i32) = match(depth >= ASPIRATION_DEPTH && previous_score.abs() < MATE_BOUND) {
+            true => ((previous_score - delta).max(-INFINITY), (previous_score + delta).min(INFINITY)),
+            false => (-INFINITY, INFINITY),
+        };
+        loop {
+            let score: i32 = self.search_root(root_moves, depth, alpha, beta);
+            if(self.stopped) {
+                return score;
+            }
+            if(score <= alpha && alpha > -INFINITY) {
+                // No root move reached alpha so there's no new PV, the previous one is what we still play
+                self.print_info(depth, score, tt::BOUND_UPPER, previous_pv);
+                beta = (alpha + beta) / 2;
+                alpha = (score - delta).max(-INFINITY);
+            } else if(score >= beta && beta < INFINITY) {
+                let pv: Vec<Move> = self.pv_table[0].clone();
+                self.print_info(depth, score, tt::BOUND_LOWER, &pv);
+                beta = (score + delta).min(INFINITY);
+            } else {
+                return score;
+            }
+            delta += delta / 2;
+        }
+    }
+
+    // Searches every root move and moves the best one to the front so the next iteration starts with it.
+    // The first move gets the full window, the others a null window that is only widened when they beat alpha.
+    fn search_root(&mut self, root_moves: &mut [Move], depth: u32, mut alpha: i32, beta: i32) -> i32 {
+        self.pv_table[0].clear();
+        for i in 0..root_moves.len() {
+            let root_move: Move = root_moves[i];
+            self.position.do_move(root_move);
+            self.nodes += 1;
+            let mut score: i32 = beta;
+            if(i > 0) {
+                score = -self.negamax(depth - 1, 1, -alpha - 1, -alpha);
+            }
+            if(i == 0 || (score > alpha && score < beta)) {
+                score = -self.negamax(depth - 1, 1, -beta, -alpha);
+            }
+            self.position.undo_move().expect("a move was just made");
+            if(self.stopped) {
+                break;
+            }
+            if(score > alpha) {
+                alpha = score;
+                self.update_pv(0, root_move);
+                root_moves[..=i].rotate_right(1);
+                if(score >= beta) {
+                    return beta;
+                }
+            }
+        }
+        return alpha;
+    }
+
+    fn negamax(&mut self, depth: u32, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        self.pv_table[ply].clear();
+        if(self.should_stop()) {
+            return 0;
+        }
+        if(depth == 0 || ply >= MAX_PLY) {
+            return self.quiescence(ply, alpha, beta);
+        }
+
+        if(self.is_draw()) {
+            return 0;
+        }
+
+        let mut tt_move: Move = Move::NULL;
+        if let Some(entry) = self.tt.probe(self.position.key) {
+            tt_move = entry.best_move();
+            if(entry.depth as u32 >= depth) {
+                let tt_score: i32 = tt::score_from_tt(entry.score as i32, ply);
+                let cutoff: bool = match(entry.bound()) {
+                    tt::BOUND_EXACT => true,
+                    tt::BOUND_LOWER => tt_score >= beta,
+                    tt::BOUND_UPPER => tt_score <= alpha,
+                    _ => false,
+                };
+                if(cutoff) {
+                    return tt_score;
+                }
+            }
+        }
+
+        let is_in_check: bool = self.position.in_check();
+        let pv_node: bool = beta - alpha > 1;
+        if(!pv_node && self.can_null_move(depth, beta, is_in_check)) {
+            let static_eval: i32 = eval::evaluate(&self.position);
+            if(static_eval >= beta) {
+                let reduction: u32 = self.pruning.null_move_reduction(depth, static_eval - beta);
+                self.position.make_null_move();
+                self.nodes += 1;
+                let score: i32 = -self.negamax(depth.saturating_sub(1 + reduction), ply + 1, -beta, -beta + 1);
+                self.position.unmake_null_move();
+                if(self.stopped) {
+                    return 0;
+                }
+                if(score >= beta) {
+                    return beta;
+                }
+            }
+        }
+
+        let legal_moves: Vec<Move> = self.position.generate_legal_moves();
+        if(legal_moves.is_empty()) {
+            if(is_in_check) {
+                return -MATE + ply as i32;
+            }
+            return 0;
+        }
+        let mut picker: MovePicker = MovePicker::new(&self.position, legal_moves, tt_move, &self.history, ply);
+
+        let mut best_move: Move = Move::NULL;
+        let mut bound: u8 = tt::BOUND_UPPER;
+        let mut quiets_tried: Vec<Move> = Vec::new();
+        let mut captures_tried: Vec<Move> = Vec::new();
+        let mut move_number: usize = 0;
+        while let Some(chess_move) = picker.next(&self.position, &self.history) {
+            let is_quiet: bool = history::is_quiet(chess_move);
+            let history_score: i32 = match(is_quiet) {
+                true => self.history.quiet_score(&self.position, chess_move),
+                false => self.history.capture_score(&self.position, chess_move),
+            };
+            self.position.do_move(chess_move);
+            self.nodes += 1;
+            // Late moves rarely turn out best, look at them with a reduced depth first and only search them
+            // fully when they beat alpha anyway. The captures that come this late are the ones SEE expects to
+            // lose material, but they change the material balance, which a shallow search misjudges more easily
+            // than a quiet move, so they are reduced one ply less.
+            let mut reduction: u32 = 0;
+            if(depth >= 3 && move_number >= 3 && !chess_move.is_promotion() && !is_in_check && !self.position.in_check()) {
+                reduction = self.pruning.late_move_reduction(depth, move_number, history_score).min(depth - 2);
+                if(!is_quiet) {
+                    reduction = reduction.saturating_sub(1);
+                }
+            }
+            // Principal variation search: past the first move, only prove the move doesn't beat alpha with a
+            // null window, and search again with the full window when it does
+            let mut score: i32 = beta;
+            if(move_number > 0) {
+                score = -self.negamax(depth - 1 - reduction, ply + 1, -alpha - 1, -alpha);
+                if(score > alpha && reduction > 0) {
+                    score = -self.negamax(depth - 1, ply + 1, -alpha - 1, -alpha);
+                }
+            }
+            if(move_number == 0 || (score > alpha && score < beta)) {
+                score = -self.negamax(depth - 1, ply + 1, -beta, -alpha);
+            }
+            self.position.undo_move().expect("a move was just made");
+            if(self.stopped) {
+                return 0;
+            }
+            if(score >= beta) {
+                self.history.update(&self.position, ply, chess_move, &quiets_tried, &captures_tried, depth);
+                self.tt.store(self.position.key, depth, beta, tt::BOUND_LOWER, chess_move, ply);
+                return beta;
+            }
+            if(score > alpha) {
+                alpha = score;
+                best_move = chess_move;
+                bound = tt::BOUND_EXACT;
+                self.update_pv(ply, chess_move);
+            }
+            match(is_quiet) {
+                true => quiets_tried.push(chess_move),
+                false => captures_tried.push(chess_move),
+            }
+            move_number += 1;
+        }
+        self.tt.store(self.position.key, depth, alpha, bound, best_move, ply);
+        return alpha;
+    }
+
+    // Giving the opponent a free move and still failing high means the position is good enough to prune.
+    // Not in check where passing is illegal, nor twice in a row, nor with only pawns left where zugzwang is common.
+    fn can_null_move(&self, depth: u32, beta: i32, is_in_check: bool) -> bool {
+        if(!self.pruning.null_move_enabled() || is_in_check || depth < 3 || beta.abs() >= MATE_BOUND) {
+            return false;
+        }
+        if(self.position.history.last().is_some_and(|record| record.chess_move == Move::NULL)) {
+            return false;
+        }
+        let bitboards: &Bitboards = &self.position.bitboards;
+        let pawns_and_king: Bitboard = bitboards.pieces[0b001] | bitboards.pieces[0b110];
+        return bitboards.colors[self.position.state.color as usize] & !pawns_and_king != 0;
+    }
+
+    // Resolves captures and promotions until the position is quiet, so the evaluation isn't taken mid-exchange.
+    // In check every evasion is searched and standing pat isn't allowed.
+    fn quiescence(&mut self, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        self.pv_table[ply].clear();
+        if(self.should_stop()) {
+            return 0;
+        }
+        if(ply >= MAX_PLY) {
+            return eval::evaluate(&self.position);
+        }
+
+        let is_in_check: bool = self.position.in_check();
+        let mut moves: Vec<Move>;
+        if(is_in_check) {
+            moves = self.position.generate_legal_moves();
+            if(moves.is_empty()) {
+                return -MATE + ply as i32;
+            }
+            ordering::order_moves(&self.position, &mut moves, Move::NULL);
+        } else {
+            let stand_pat: i32 = eval::evaluate(&self.position);
+            if(stand_pat >= beta) {
+                return beta;
+            }
+            if(stand_pat > alpha) {
+                alpha = stand_pat;
+            }
+            moves = self.position.generate_captures();
+            // A capture that loses material on the exchange can't raise a stand pat score, the rest
+            // are all winning or even so MVV-LVA alone orders them
+            moves.retain(|&chess_move| self.position.see_ge(chess_move, 0));
+            ordering::order_captures(&self.position, &mut moves);
+        }
+
+        for chess_move in moves {
+            self.position.do_move(chess_move);
+            self.nodes += 1;
+            let score: i32 = -self.quiescence(ply + 1, -beta, -alpha);
+            self.position.undo_move().expect("a move was just made");
+            if(self.stopped) {
+                return 0;
+            }
+            if(score >= beta) {
+                return beta;
+            }
+            if(score > alpha) {
+                alpha = score;
+                self.update_pv(ply, chess_move);
+            }
+        }
+        return alpha;
+    }
+
+    // Inside the search a single repetition is enough, the side that could avoid it would have already.
+    // Mate takes precedence over the fifty-move rule, as in Position::game_result.
+    fn is_draw(&self) -> bool {
+        let fifty_moves: bool = self.position.state.halfmove_clock >= 100
+            && (!self.position.in_check() || !self.position.generate_legal_moves().is_empty());
+        return fifty_moves
+            || self.position.repetition_count() >= 2
+            || self.position.has_insufficient_material();
+    }
+
+    fn update_pv(&mut self, ply: usize, chess_move: Move) {
+        let mut pv: Vec<Move> = vec![chess_move];
+        pv.extend_from_slice(&self.pv_table[ply + 1]);
+        self.pv_table[ply] = pv;
+    }
+
+    fn should_stop(&mut self) -> bool {
+        if(self.stopped) {
+            return true;
+        }
+        if(self.signals.stop.load(Ordering::Relaxed) || self.limits.nodes.is_some_and(|nodes| self.nodes >= nodes)) {
+            self.stopped = true;
+        } else if(self.nodes & 1023 == 0 && !self.limits.infinite) {
+            self.update_pondering();
+            if(!self.pondering && self.time.hard_limit_reached()) {
+                self.stopped = true;
+            }
+        }
+        return self.stopped;
+    }
+
+    // Our clock starts on "ponderhit", until then the time limits don't apply
+    fn update_pondering(&mut self) {
+        if(self.pondering && !self.signals.ponder.load(Ordering::Relaxed)) {
+            self.pondering = false;
+            self.time.restart();
+        }
+    }
+
+    // A bound other than exact marks a score that fell outside the aspiration window
+    fn print_info(&self, depth: u32, score: i32, bound: u8, pv: &[Move]) {
+        let elapsed_ms: u64 = self.time.elapsed();
+        let nps: u64 = (self.nodes * 1000) / elapsed_ms.max(1);
+        let pv_string: String = pv.iter()
+            .map(|chess_move| chess_move.to_string())
+            .collect::<Vec<String>>()
+            .join(" ");
+        let bound_string: &str = match(bound) {
+            tt::BOUND_LOWER => " lowerbound",
+            tt::BOUND_UPPER => " upperbound",
+            _ => "",
+        };
+        println!(
+            "info depth {} score {}{} nodes {} nps {} hashfull {} time {} pv {}",
+            depth,
+            format_score(score),
+            bound_string,
+            self.nodes,
+            nps,
+            self.tt.hashfull(),
+            elapsed_ms,
+            pv_string
+        );
+    }
+}
+
+// UCI "score" field, mates are given in moves rather than plies
+pub fn format_score(score: i32) -> String {
+    if(score >= MATE_BOUND) {
+        return format!("mate {}", (MATE - score + 1) / 2);
+    }
+    if(score <= -MATE_BOUND) {
+        return format!("mate -{}", (MATE + score) / 2);
+    }
+    return format!("cp {}", score);
+}
+
+// Runs the search on its own thread so the UCI loop can still answer "isready", "stop" and "ponderhit"
+pub fn start(position: Position, params: &GoParams, options: &Options, tt: Arc<Mutex<TranspositionTable>>, signals: Arc<Signals>) -> std::thread::JoinHandle<()> {
+    let limits: Limits = Limits::from(params);
+    let time: TimeManager = TimeManager::new(params, position.state.color, options.move_overhead);
+    let pruning: Pruning = Pruning::from(options);
+    signals.stop.store(false, Ordering::SeqCst);
+    signals.ponder.store(params.ponder, Ordering::SeqCst);
+    return std::thread::spawn(move || {
+        let mut tt = tt.lock().expect("transposition table poisoned");
+        tt.new_search();
+        let mut search: Search = Search::new(position, limits, time, pruning, &mut tt, signals.clone());
+        let (best_move, pv): (Move, Vec<Move>) = search.run();
+
+        // bestmove can't be sent before "stop" in infinite mode, nor before "ponderhit" or "stop" while pondering
+        while(!signals.stop.load(Ordering::SeqCst) && (search.limits.infinite || signals.ponder.load(Ordering::SeqCst))) {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+
+        match(pv.get(1)) {
+            Some(ponder_move) => println!("bestmove {} ponder {}", best_move, ponder_move),
+            None => println!("bestmove {}", best_move),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn can_null_move(fen: &str, null_move_first: bool) -> bool {
+        let mut position: Position = Position::from(fen);
+        if(null_move_first) {
+            position.make_null_move();
+        }
+        let mut tt: TranspositionTable = TranspositionTable::new(1);
+        let time: TimeManager = TimeManager::new(&GoParams::default(), false, 0);
+        let search: Search = Search::new(position, Limits::default(), time, Pruning::from(&Options::new()), &mut tt, Arc::new(Signals::new()));
+        let is_in_check: bool = search.position.in_check();
+        return search.can_null_move(6, 50, is_in_check);
+    }
+
+    #[test]
+    fn guards_null_moves() {
+        assert!(can_null_move("4k3/pp6/8/8/8/8/PP6/4K1N1 w - - 0 1", false));
+        // Kings and pawns only, where zugzwang is common
+        assert!(!can_null_move("4k3/pp6/8/8/8/8/PP6/4K3 w - - 0 1", false));
+        // Only the side to move's pieces count
+        assert!(!can_null_move("4k1n1/pp6/8/8/8/8/PP6/4K3 w - - 0 1", false));
+        // In check
+        assert!(!can_null_move("4k3/pp6/8/8/7b/8/PP6/4K1N1 w - - 0 1", false));
+        // Twice in a row
+        assert!(!can_null_move("4k1n1/pp6/8/8/8/8/PP6/4K1N1 b - - 0 1", true));
+    }
+
+    #[test]
+    fn stops_on_proven_mates() {
+        let mut tt: TranspositionTable = TranspositionTable::new(1);
+        let params: GoParams = GoParams { mate: Some(1), ..GoParams::default() };
+        let time: TimeManager = TimeManager::new(&params, false, 0);
+        let position: Position = Position::from("6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1");
+        let mut search: Search = Search::new(position, Limits::from(&params), time, Pruning::from(&Options::new()), &mut tt, Arc::new(Signals::new()));
+        assert_eq!(search.run().0.to_string(), "d1d8");
+
+        // A mate in 2 found at depth 2 could still be beaten by a shorter one hidden by reductions
+        search.limits = Limits::default();
+        assert!(!search.mate_is_proven(MATE - 3, 2));
+        assert!(search.mate_is_proven(MATE - 3, 3));
+        assert!(search.mate_is_proven(-MATE + 2, 2));
+        assert!(!search.mate_is_proven(500, 40));
+        // Unless it's what "go mate" asked for
+        search.limits.mate = Some(2);
+        assert!(search.mate_is_proven(MATE - 3, 2));
+        assert!(!search.mate_is_proven(-MATE + 2, 1));
+        search.limits.mate = Some(1);
+        assert!(!search.mate_is_proven(MATE - 3, 2));
+    }
+
+    #[test]
+    fn mates_on_the_hundredth_ply() {
+        let mut tt: TranspositionTable = TranspositionTable::new(1);
+        let time: TimeManager = TimeManager::new(&GoParams::default(), false, 0);
+        let limits: Limits = Limits { depth: Some(2), ..Limits::default() };
+        let position: Position = Position::from("7k/8/6K1/8/8/8/8/R7 w - - 99 80");
+        let mut search: Search = Search::new(position, limits, time, Pruning::from(&Options::new()), &mut tt, Arc::new(Signals::new()));
+        assert_eq!(search.run().0.to_string(), "a1a8");
+
+        search.position.make_move(Move::from_uci("a1a8", &search.position).unwrap()).unwrap();
+        assert!(!search.is_draw());
+        assert_eq!(search.negamax(1, 1, -INFINITY, INFINITY), -MATE + 1);
+        search.position.unmake_move().unwrap();
+        search.position.make_move(Move::from_uci("a1a2", &search.position).unwrap()).unwrap();
+        assert!(search.is_draw());
+    }
+
+    // Best move and score of a fresh search at the given depth, either with the full window or with an aspiration
+    // window centered on a guess far enough off that it fails high or low first
+    fn search_depth(fen: &str, depth: u32, guess: Option<i32>) -> (Move, i32) {
+        let mut tt: TranspositionTable = TranspositionTable::new(16);
+        let time: TimeManager = TimeManager::new(&GoParams::default(), false, 0);
+        let position: Position = Position::from(fen);
+        let mut search: Search = Search::new(position, Limits::default(), time, Pruning::from(&Options::new()), &mut tt, Arc::new(Signals::new()));
+        let mut root_moves: Vec<Move> = search.position.generate_legal_moves();
+        ordering::order_moves(&search.position, &mut root_moves, Move::NULL);
+        let score: i32 = match(guess) {
+            Some(v) => search.aspiration_search(&mut root_moves, depth, v, &[]),
+            None => search.search_root(&mut root_moves, depth, -INFINITY, INFINITY),
+        };
+        return (root_moves[0], score);
+    }
+
+    #[test]
+    fn researches_aspiration_failures() {
+        for fen in [
+            "2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        ] {
+            let (best_move, score): (Move, i32) = search_depth(fen, ASPIRATION_DEPTH, None);
+            // Fails low first
+            assert_eq!(search_depth(fen, ASPIRATION_DEPTH, Some(score + 200)), (best_move, score), "{}", fen);
+            // Fails high first
+            assert_eq!(search_depth(fen, ASPIRATION_DEPTH, Some(score - 200)), (best_move, score), "{}", fen);
+        }
+    }
+
+    #[test]
+    fn takes_back_null_moves() {
+        let mut position: Position = Position::from("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1");
+        let (fen, key): (String, u64) = (position.fen(), position.key);
+        position.make_null_move();
+        assert!(!position.state.color);
+        assert_eq!(position.state.en_passant, 64);
+        assert_eq!(position.key, position.compute_key());
+        assert_ne!(position.key, key);
+        position.unmake_null_move();
+        assert_eq!(position.fen(), fen);
+        assert_eq!(position.key, key);
+        assert!(position.history.is_empty());
+    }
+}
@@ -0,0 +1,46 @@
+use super::super::position::{moves::Move, Position};
+
+// Piece values used for ordering only, indexed by piece type (see Piece.data)
+const ORDERING_VALUES: [i32; 7] = [0, 100, 320, 330, 500, 900, 10000];
+
+const TT_MOVE_SCORE: i32 = 1_000_000;
+const CAPTURE_SCORE: i32 = 100_000;
+
+// Most valuable victim first, and for the same victim the least valuable attacker first
+pub fn mvv_lva(position: &Position, chess_move: Move) -> i32 {
+    let attacker: usize = (position.state.board[chess_move.origin() as usize].data & 0b111) as usize;
+    let victim: usize = match(chess_move.is_en_passant()) {
+        true => 0b001,
+        false => (position.state.board[chess_move.target() as usize].data & 0b111) as usize,
+    };
+    return ORDERING_VALUES[victim] * 10 - ORDERING_VALUES[attacker] / 10;
+}
+
+// MVV-LVA plus the value of the promotion piece, if any
+pub fn capture_score(position: &Position, chess_move: Move) -> i32 {
+    return mvv_lva(position, chess_move) + ORDERING_VALUES[chess_move.promotion() as usize];
+}
+
+// The transposition table move first, then captures and promotions by MVV-LVA, then quiet moves,
+// then the captures that lose material on the exchange
+pub fn order_moves(position: &Position, moves: &mut [Move], tt_move: Move) {
+    moves.sort_by_cached_key(|&chess_move| {
+        let score: i32 = if(chess_move == tt_move) {
+            TT_MOVE_SCORE
+        } else if(chess_move.is_capture() || chess_move.is_promotion()) {
+            let capture_score: i32 = capture_score(position, chess_move);
+            match(position.see_ge(chess_move, 0)) {
+                true => CAPTURE_SCORE + capture_score,
+                false => -CAPTURE_SCORE + capture_score,
+            }
+        } else {
+            0
+        };
+        return -score;
+    });
+}
+
+// Captures and promotions by MVV-LVA only, for lists already stripped of losing captures
+pub fn order_captures(position: &Position, moves: &mut [Move]) {
+    moves.sort_by_cached_key(|&chess_move| -capture_score(position, chess_move));
+}
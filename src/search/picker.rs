@@ -0,0 +1,170 @@
+use super::super::position::{moves::Move, Position};
+use super::history::History;
+use super::ordering;
+
+// Scales capture history down so it only reorders captures of similar MVV-LVA value
+const CAPTURE_HISTORY_DIVISOR: i32 = 16;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Stage {
+    TtMove,
+    GoodCaptures,
+    Refutations,
+    Quiets,
+    BadCaptures,
+    Done,
+}
+
+// Hands out the legal moves of a node one at a time, best guess first: the transposition table move,
+// captures and promotions that don't lose material by MVV-LVA and capture history, the killers and the countermove,
+// the remaining quiet moves by butterfly and continuation history, then the losing captures.
+// Each stage is only scored when it's reached, and most nodes cut off long before the last ones.
+pub struct MovePicker {
+    stage: Stage,
+    tt_move: Move,
+    noisy: Vec<(Move, i32)>,
+    quiets: Vec<(Move, i32)>,
+    bad_captures: Vec<Move>,
+    refutations: [Move; 3],
+    index: usize, // Position in refutations or bad_captures during those stages
+}
+
+impl MovePicker {
+    pub fn new(position: &Position, legal_moves: Vec<Move>, tt_move: Move, history: &History, ply: usize) -> MovePicker {
+        let mut picker: MovePicker = MovePicker {
+            stage: Stage::TtMove,
+            tt_move: Move::NULL,
+            noisy: Vec::with_capacity(16),
+            quiets: Vec::with_capacity(legal_moves.len()),
+            bad_captures: Vec::new(),
+            refutations: [Move::NULL; 3],
+            index: 0,
+        };
+        for chess_move in legal_moves {
+            if(chess_move == tt_move) {
+                picker.tt_move = tt_move;
+            } else if(chess_move.is_capture() || chess_move.is_promotion()) {
+                picker.noisy.push((chess_move, 0));
+            } else {
+                picker.quiets.push((chess_move, 0));
+            }
+        }
+        let killers: [Move; 2] = history.killers(ply);
+        picker.refutations = [killers[0], killers[1], history.countermove(position)];
+        return picker;
+    }
+
+    pub fn next(&mut self, position: &Position, history: &History) -> Option<Move> {
+        loop {
+            match(self.stage) {
+                Stage::TtMove => {
+                    for (chess_move, score) in self.noisy.iter_mut() {
+                        *score = ordering::capture_score(position, *chess_move) + history.capture_score(position, *chess_move) / CAPTURE_HISTORY_DIVISOR;
+                    }
+                    self.stage = Stage::GoodCaptures;
+                    if(self.tt_move != Move::NULL) {
+                        return Some(self.tt_move);
+                    }
+                }
+                Stage::GoodCaptures => {
+                    match(pick_best(&mut self.noisy)) {
+                        Some(chess_move) if position.see_ge(chess_move, 0) => return Some(chess_move),
+                        Some(chess_move) => self.bad_captures.push(chess_move),
+                        None => self.stage = Stage::Refutations,
+                    }
+                }
+                Stage::Refutations => {
+                    if(self.index == self.refutations.len()) {
+                        for (chess_move, score) in self.quiets.iter_mut() {
+                            *score = history.quiet_score(position, *chess_move);
+                        }
+                        self.index = 0;
+                        self.stage = Stage::Quiets;
+                        continue;
+                    }
+                    let refutation: Move = self.refutations[self.index];
+                    self.index += 1;
+                    // Taking it out of the quiet moves checks it's legal here and keeps it from coming up twice
+                    if let Some(i) = self.quiets.iter().position(|&(chess_move, _)| chess_move == refutation) {
+                        self.quiets.swap_remove(i);
+                        return Some(refutation);
+                    }
+                }
+                Stage::Quiets => {
+                    match(pick_best(&mut self.quiets)) {
+                        Some(chess_move) => return Some(chess_move),
+                        None => self.stage = Stage::BadCaptures,
+                    }
+                }
+                Stage::BadCaptures => {
+                    if(self.index < self.bad_captures.len()) {
+                        self.index += 1;
+                        return Some(self.bad_captures[self.index - 1]);
+                    }
+                    self.stage = Stage::Done;
+                }
+                Stage::Done => return None,
+            }
+        }
+    }
+}
+
+// Removes and returns the highest scored move
+fn pick_best(moves: &mut Vec<(Move, i32)>) -> Option<Move> {
+    let best: usize = (0..moves.len()).max_by_key(|&i| moves[i].1)?;
+    return Some(moves.swap_remove(best).0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_moves_in_stages() {
+        let mut position: Position = Position::from("4k3/4p3/8/3p4/n7/2N5/1Q6/R3K3 b - - 0 1");
+        position.make_move(Move::from_uci("e7e6", &position).unwrap()).unwrap();
+        let uci = |uci: &str| -> Move { Move::from_uci(uci, &position).unwrap() };
+
+        let mut history: History = History::new();
+        history.update(&position, 0, uci("b2b5"), &[], &[], 4);
+        history.update(&position, 0, uci("b2b6"), &[], &[], 4);
+        // Cutting off at another ply leaves the killers alone but still makes it the countermove to e7e6
+        history.update(&position, 5, uci("e1f1"), &[], &[], 4);
+
+        let legal_moves: Vec<Move> = position.generate_legal_moves();
+        let mut picker: MovePicker = MovePicker::new(&position, legal_moves.clone(), uci("b2b3"), &history, 0);
+        let mut picked: Vec<Move> = Vec::new();
+        while let Some(chess_move) = picker.next(&position, &history) {
+            picked.push(chess_move);
+        }
+
+        let mut sorted: Vec<Move> = picked.clone();
+        sorted.sort_by_key(|chess_move| chess_move.data);
+        sorted.dedup();
+        assert_eq!(sorted.len(), picked.len());
+        assert_eq!(picked.len(), legal_moves.len());
+
+        assert_eq!(picked[0], uci("b2b3"));
+        // Both captures of the undefended knight, the pawn on d5 is defended
+        assert!(picked[1..3].contains(&uci("c3a4")) && picked[1..3].contains(&uci("a1a4")));
+        assert_eq!(picked[3..6], [uci("b2b6"), uci("b2b5"), uci("e1f1")]);
+        assert!(picked[6..picked.len() - 1].iter().all(|chess_move| !chess_move.is_capture()));
+        assert_eq!(picked[picked.len() - 1], uci("c3d5"));
+    }
+
+    #[test]
+    fn skips_missing_refutations() {
+        let position: Position = Position::new();
+        let mut history: History = History::new();
+        // A killer from another position that isn't legal here
+        history.update(&Position::from("4k3/8/8/8/8/8/8/R3K3 w - - 0 1"), 0, Move::new(0, 56, 0), &[], &[], 4);
+        let legal_moves: Vec<Move> = position.generate_legal_moves();
+        let mut picker: MovePicker = MovePicker::new(&position, legal_moves, Move::NULL, &history, 0);
+        let mut count: usize = 0;
+        while let Some(chess_move) = picker.next(&position, &history) {
+            assert_ne!(chess_move, Move::new(0, 56, 0));
+            count += 1;
+        }
+        assert_eq!(count, 20);
+    }
+}
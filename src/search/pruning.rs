@@ -0,0 +1,86 @@
+use super::super::uci::Options;
+use super::history::MAX_HISTORY;
+
+// History score worth one ply of reduction
+const HISTORY_REDUCTION_DIVISOR: i32 = MAX_HISTORY / 2;
+
+// Null-move and late move reduction parameters, read from the UCI options when a search starts
+#[derive(Clone)]
+pub struct Pruning {
+    null_move_reduction: u32,
+    null_move_divisor: u32,
+    reductions: [[u8; 64]; 64], // Indexed by remaining depth then move number, both capped at 63
+}
+
+impl Pruning {
+    pub fn from(options: &Options) -> Pruning {
+        let base: f64 = options.lmr_base as f64 / 100.0;
+        let divisor: f64 = options.lmr_divisor as f64 / 100.0;
+        let mut reductions: [[u8; 64]; 64] = [[0; 64]; 64];
+        for (depth, row) in reductions.iter_mut().enumerate().skip(1) {
+            for (move_number, reduction) in row.iter_mut().enumerate().skip(1) {
+                *reduction = (base + (depth as f64).ln() * (move_number as f64).ln() / divisor) as u8;
+            }
+        }
+        return Pruning {
+            null_move_reduction: options.null_move_reduction,
+            null_move_divisor: options.null_move_divisor.max(1),
+            reductions,
+        };
+    }
+
+    pub fn null_move_enabled(&self) -> bool {
+        return self.null_move_reduction > 0;
+    }
+
+    // Reduces more at higher depths and the further the static evaluation is above beta
+    pub fn null_move_reduction(&self, depth: u32, eval_margin: i32) -> u32 {
+        return self.null_move_reduction + depth / self.null_move_divisor + (eval_margin / 200).clamp(0, 3) as u32;
+    }
+
+    // Plies taken off a late move, move_number counting from 0 in the ordered list. Moves with a good
+    // history score are reduced less and those with a bad one more.
+    pub fn late_move_reduction(&self, depth: u32, move_number: usize, history_score: i32) -> u32 {
+        let reduction: i32 = self.reductions[(depth as usize).min(63)][move_number.min(63)] as i32 - history_score / HISTORY_REDUCTION_DIVISOR;
+        return reduction.max(0) as u32;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reduces_late_moves() {
+        let pruning: Pruning = Pruning::from(&Options::new());
+        for depth in 1..64 {
+            for move_number in 1..64 {
+                let reduction: u32 = pruning.late_move_reduction(depth, move_number, 0);
+                assert!(reduction >= pruning.late_move_reduction(depth - 1, move_number, 0));
+                assert!(reduction >= pruning.late_move_reduction(depth, move_number - 1, 0));
+            }
+        }
+        assert_eq!(pruning.late_move_reduction(1, 1, 0), 0);
+        assert!(pruning.late_move_reduction(20, 30, 0) >= 2);
+        // Good history takes plies off down to no reduction at all, bad history adds some
+        assert_eq!(pruning.late_move_reduction(20, 30, 3 * MAX_HISTORY), 0);
+        assert!(pruning.late_move_reduction(20, 30, -MAX_HISTORY) > pruning.late_move_reduction(20, 30, 0));
+        // Past the end of the table the last entries are used
+        assert_eq!(pruning.late_move_reduction(200, 500, 0), pruning.late_move_reduction(63, 63, 0));
+    }
+
+    #[test]
+    fn reduces_null_moves() {
+        let pruning: Pruning = Pruning::from(&Options::new());
+        assert!(pruning.null_move_enabled());
+        assert_eq!(pruning.null_move_reduction(3, 0), 3);
+        assert_eq!(pruning.null_move_reduction(12, 0), 5);
+        assert_eq!(pruning.null_move_reduction(12, 450), 7);
+        // The margin adds at most 3 plies
+        assert_eq!(pruning.null_move_reduction(12, 5000), 8);
+
+        let mut options: Options = Options::new();
+        options.null_move_reduction = 0;
+        assert!(!Pruning::from(&options).null_move_enabled());
+    }
+}
@@ -0,0 +1,118 @@
+use std::time::Instant;
+
+use super::super::uci::GoParams;
+
+// Moves the engine assumes are left in sudden death, and the most it ever plans for with movestogo
+const DEFAULT_MOVES_TO_GO: u64 = 30;
+const MAX_MOVES_TO_GO: u64 = 50;
+
+// How much of the soft limit may be used depending on how many iterations in a row kept the same best move
+const STABILITY_SCALE: [u64; 5] = [250, 130, 100, 80, 70]; // In percent
+
+pub struct TimeManager {
+    start: Instant,
+    soft_limit: Option<u64>, // No new iteration is started past this, in milliseconds
+    hard_limit: Option<u64>, // The search is aborted past this, in milliseconds
+}
+
+impl TimeManager {
+    pub fn new(params: &GoParams, color: bool, move_overhead: u64) -> TimeManager {
+        let mut manager: TimeManager = TimeManager {
+            start: Instant::now(),
+            soft_limit: None,
+            hard_limit: None,
+        };
+        if(params.infinite) {
+            return manager;
+        }
+        if let Some(movetime) = params.movetime {
+            let limit: u64 = movetime.saturating_sub(move_overhead).max(1);
+            manager.soft_limit = Some(limit);
+            manager.hard_limit = Some(limit);
+            return manager;
+        }
+
+        let (time_left, increment): (Option<u64>, u64) = match(color) {
+            false => (params.wtime, params.winc.unwrap_or(0)),
+            true => (params.btime, params.binc.unwrap_or(0)),
+        };
+        let time_left: u64 = match(time_left) {
+            Some(v) => v,
+            None => return manager,
+        };
+
+        // The overhead is paid on this move before the GUI stops our clock, so it is kept out of the budget
+        let moves_to_go: u64 = params.movestogo
+            .map(|moves| moves as u64)
+            .unwrap_or(DEFAULT_MOVES_TO_GO)
+            .clamp(1, MAX_MOVES_TO_GO);
+        let available: u64 = time_left.saturating_sub(move_overhead).max(1);
+
+        let soft_limit: u64 = (available / moves_to_go + increment * 3 / 4).min(available / 2);
+        let hard_limit: u64 = (soft_limit * 4).min(available * 3 / 4).max(soft_limit);
+        manager.soft_limit = Some(soft_limit.max(1));
+        manager.hard_limit = Some(hard_limit.max(1));
+        return manager;
+    }
+
+    pub fn elapsed(&self) -> u64 {
+        return self.start.elapsed().as_millis() as u64;
+    }
+
+    // The clock only starts running for us once a pondered move is played
+    pub fn restart(&mut self) {
+        self.start = Instant::now();
+    }
+
+    pub fn hard_limit_reached(&self) -> bool {
+        return self.hard_limit.is_some_and(|limit| self.elapsed() >= limit);
+    }
+
+    // Checked between iterations, a best move that kept coming back lets the search stop earlier
+    pub fn soft_limit_reached(&self, stability: usize) -> bool {
+        return match(self.soft_limit) {
+            Some(limit) => {
+                let scaled_limit: u64 = limit * STABILITY_SCALE[stability.min(STABILITY_SCALE.len() - 1)] / 100;
+                self.elapsed() >= scaled_limit.min(self.hard_limit.unwrap_or(u64::MAX))
+            }
+            None => false,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(go: &str, color: bool) -> (Option<u64>, Option<u64>) {
+        let tokens: Vec<String> = go.split_whitespace().map(|token| token.to_string()).collect::<Vec<String>>();
+        let manager: TimeManager = TimeManager::new(&GoParams::parse(&tokens), color, 10);
+        return (manager.soft_limit, manager.hard_limit);
+    }
+
+    #[test]
+    fn budgets_time() {
+        // Sudden death plans for 30 more moves
+        assert_eq!(limits("wtime 60000 btime 60000", false), (Some(1999), Some(7996)));
+        // Three quarters of the increment are spent on top
+        assert_eq!(limits("wtime 10000 btime 5000 winc 1000 binc 1000", false), (Some(1083), Some(4332)));
+        assert_eq!(limits("wtime 10000 btime 5000 winc 1000 binc 1000", true), (Some(916), Some(3664)));
+        // With movestogo the clock is split over the moves left before it's refilled
+        assert_eq!(limits("wtime 10000 btime 10000 movestogo 10", false), (Some(999), Some(3996)));
+        assert_eq!(limits("wtime 10000 btime 10000 movestogo 1", false), (Some(4995), Some(7492)));
+        // A fixed move time is used as is, minus the overhead
+        assert_eq!(limits("movetime 1000", false), (Some(990), Some(990)));
+        assert_eq!(limits("infinite", false), (None, None));
+        assert_eq!(limits("depth 5", false), (None, None));
+    }
+
+    #[test]
+    fn never_flags() {
+        // The overhead is taken once, leaving something to search with
+        assert_eq!(limits("wtime 100 btime 100", false), (Some(3), Some(12)));
+        // Past the flag or below the overhead the search still stops right away instead of running unbounded
+        assert_eq!(limits("wtime 5 btime 100", false), (Some(1), Some(1)));
+        assert_eq!(limits("wtime -20 btime 100", false), (Some(1), Some(1)));
+        assert_eq!(limits("wtime 100 btime 100 winc -5", false), (Some(3), Some(12)));
+    }
+}
@@ -0,0 +1,236 @@
+use super::super::position::moves::Move;
+use super::MATE_BOUND;
+
+pub const BOUND_NONE: u8 = 0b00;
+pub const BOUND_UPPER: u8 = 0b01; // Every move failed low, the score is at most this
+pub const BOUND_LOWER: u8 = 0b10; // A move failed high, the score is at least this
+pub const BOUND_EXACT: u8 = 0b11;
+
+const BUCKET_SIZE: usize = 4;
+
+#[derive(Copy, Clone, Default)]
+pub struct Entry {
+    pub key: u64,
+    pub best_move: u16, // Move.data, 0 when no move is known
+    pub score: i16,
+    pub depth: u8,
+    pub age_bound: u8, // AAAAAABB where A = age of the search that stored it, B = bound type
+}
+
+impl Entry {
+    pub fn bound(&self) -> u8 {
+        return self.age_bound & 0b11;
+    }
+
+    pub fn age(&self) -> u8 {
+        return self.age_bound >> 2;
+    }
+
+    pub fn best_move(&self) -> Move {
+        return Move { data: self.best_move };
+    }
+}
+
+// Four entries fill one 64 byte cache line
+#[derive(Copy, Clone, Default)]
+#[repr(align(64))]
+struct Bucket {
+    entries: [Entry; BUCKET_SIZE],
+}
+
+pub struct TranspositionTable {
+    buckets: Vec<Bucket>,
+    age: u8, // Bumped on every new search, wraps around after 64 searches
+}
+
+impl TranspositionTable {
+    pub fn new(size_mb: usize) -> TranspositionTable {
+        let mut table: TranspositionTable = TranspositionTable {
+            buckets: Vec::new(),
+            age: 0,
+        };
+        table.resize(size_mb);
+        return table;
+    }
+
+    pub fn resize(&mut self, size_mb: usize) {
+        let bucket_count: usize = (size_mb * 1024 * 1024 / std::mem::size_of::<Bucket>()).max(1);
+        self.buckets = vec![Bucket::default(); bucket_count];
+        self.age = 0;
+    }
+
+    pub fn clear(&mut self) {
+        self.buckets.fill(Bucket::default());
+        self.age = 0;
+    }
+
+    pub fn new_search(&mut self) {
+        self.age = (self.age + 1) & 0b111111;
+    }
+
+    fn bucket_index(&self, key: u64) -> usize {
+        // Multiply-shift maps the key onto the table without a modulo
+        return ((key as u128 * self.buckets.len() as u128) >> 64) as usize;
+    }
+
+    pub fn probe(&self, key: u64) -> Option<Entry> {
+        let bucket: &Bucket = &self.buckets[self.bucket_index(key)];
+        return bucket.entries.iter()
+            .find(|entry| entry.key == key && entry.bound() != BOUND_NONE)
+            .copied();
+    }
+
+    // Overwrites the entry of the same position, otherwise the least valuable one: old searches first, then shallow ones
+    pub fn store(&mut self, key: u64, depth: u32, score: i32, bound: u8, best_move: Move, ply: usize) {
+        let age: u8 = self.age;
+        let index: usize = self.bucket_index(key);
+        let bucket: &mut Bucket = &mut self.buckets[index];
+
+        let mut replace_index: usize = 0;
+        let mut lowest_value: i32 = i32::MAX;
+        for (i, entry) in bucket.entries.iter().enumerate() {
+            if(entry.key == key || entry.bound() == BOUND_NONE) {
+                replace_index = i;
+                break;
+            }
+            let age_difference: i32 = ((age + 64 - entry.age()) & 0b111111) as i32;
+            let value: i32 = entry.depth as i32 - 8 * age_difference;
+            if(value < lowest_value) {
+                lowest_value = value;
+                replace_index = i;
+            }
+        }
+
+        let entry: &mut Entry = &mut bucket.entries[replace_index];
+        // Keep the old move when a fail-low at the same position didn't find a better one
+        let stored_move: u16 = match(best_move == Move::NULL && entry.key == key) {
+            true => entry.best_move,
+            false => best_move.data,
+        };
+        *entry = Entry {
+            key,
+            best_move: stored_move,
+            score: score_to_tt(score, ply) as i16,
+            depth: depth.min(u8::MAX as u32) as u8,
+            age_bound: (age << 2) | bound,
+        };
+    }
+
+    // Per mille of the first thousand entries written by the current search, as reported by "info hashfull"
+    pub fn hashfull(&self) -> u32 {
+        let mut used: u32 = 0;
+        for bucket in self.buckets.iter().take(1000 / BUCKET_SIZE) {
+            for entry in bucket.entries.iter() {
+                if(entry.bound() != BOUND_NONE && entry.age() == self.age) {
+                    used += 1;
+                }
+            }
+        }
+        let sampled: u32 = (self.buckets.len().min(1000 / BUCKET_SIZE) * BUCKET_SIZE) as u32;
+        return used * 1000 / sampled.max(1);
+    }
+}
+
+// Mate scores are stored relative to the node so they stay correct when reached through another path
+fn score_to_tt(score: i32, ply: usize) -> i32 {
+    if(score >= MATE_BOUND) {
+        return score + ply as i32;
+    }
+    if(score <= -MATE_BOUND) {
+        return score - ply as i32;
+    }
+    return score;
+}
+
+pub fn score_from_tt(score: i32, ply: usize) -> i32 {
+    if(score >= MATE_BOUND) {
+        return score - ply as i32;
+    }
+    if(score <= -MATE_BOUND) {
+        return score + ply as i32;
+    }
+    return score;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::MATE;
+
+    #[test]
+    fn stores_and_probes() {
+        let mut table: TranspositionTable = TranspositionTable::new(1);
+        let best_move: Move = Move::new(12, 28, 0b0001);
+        table.store(0x1234_5678_9ABC_DEF0, 7, -35, BOUND_LOWER, best_move, 3);
+        let entry: Entry = table.probe(0x1234_5678_9ABC_DEF0).unwrap();
+        assert_eq!((entry.depth, entry.score, entry.bound(), entry.best_move()), (7, -35, BOUND_LOWER, best_move));
+        assert!(table.probe(0x1234_5678_9ABC_DEF1).is_none());
+        // A fail-low without a move keeps the move already known for the position
+        table.store(0x1234_5678_9ABC_DEF0, 8, -60, BOUND_UPPER, Move::NULL, 3);
+        assert_eq!(table.probe(0x1234_5678_9ABC_DEF0).unwrap().best_move(), best_move);
+    }
+
+    #[test]
+    fn replaces_entries() {
+        // Less than a bucket's worth of memory, every key lands in the one bucket
+        let mut table: TranspositionTable = TranspositionTable::new(0);
+        for (key, depth) in [(1, 5), (2, 3), (3, 8), (4, 6)] {
+            table.store(key, depth, 0, BOUND_EXACT, Move::NULL, 0);
+        }
+        // The shallowest entry goes first
+        table.store(5, 10, 0, BOUND_EXACT, Move::NULL, 0);
+        assert!(table.probe(2).is_none());
+        // The same position is overwritten in place, even by a shallower search
+        table.store(3, 1, 0, BOUND_EXACT, Move::NULL, 0);
+        assert_eq!(table.probe(3).unwrap().depth, 1);
+        assert!([1, 4, 5].iter().all(|&key| table.probe(key).is_some()));
+
+        // Entries of an earlier search go before shallower ones of the current search
+        table.new_search();
+        table.store(6, 1, 0, BOUND_EXACT, Move::NULL, 0);
+        assert!(table.probe(3).is_none());
+        table.store(7, 1, 0, BOUND_EXACT, Move::NULL, 0);
+        assert!(table.probe(1).is_none());
+        assert!([4, 5, 6, 7].iter().all(|&key| table.probe(key).is_some()));
+    }
+
+    #[test]
+    fn adjusts_mate_scores() {
+        // Mate in 10 plies from the root found 4 plies deep is a mate in 6 from that node
+        assert_eq!(score_to_tt(MATE - 10, 4), MATE - 6);
+        assert_eq!(score_from_tt(MATE - 6, 2), MATE - 8);
+        assert_eq!(score_to_tt(-MATE + 10, 4), -MATE + 6);
+        assert_eq!(score_from_tt(-MATE + 6, 2), -MATE + 8);
+        assert_eq!(score_to_tt(250, 4), 250);
+        assert_eq!(score_from_tt(-250, 4), -250);
+
+        let mut table: TranspositionTable = TranspositionTable::new(1);
+        table.store(42, 3, MATE - 10, BOUND_EXACT, Move::NULL, 4);
+        assert_eq!(score_from_tt(table.probe(42).unwrap().score as i32, 4), MATE - 10);
+    }
+
+    #[test]
+    fn counts_hashfull() {
+        let mut table: TranspositionTable = TranspositionTable::new(0);
+        assert_eq!(table.hashfull(), 0);
+        table.store(1, 1, 0, BOUND_EXACT, Move::NULL, 0);
+        table.store(2, 1, 0, BOUND_EXACT, Move::NULL, 0);
+        assert_eq!(table.hashfull(), 500);
+        // Only the current search's entries count
+        table.new_search();
+        assert_eq!(table.hashfull(), 0);
+        table.store(3, 1, 0, BOUND_EXACT, Move::NULL, 0);
+        assert_eq!(table.hashfull(), 250);
+        table.clear();
+        assert_eq!(table.hashfull(), 0);
+        assert!(table.probe(3).is_none());
+
+        table.resize(1);
+        for key in 1..100_000u64 {
+            table.store(key.wrapping_mul(0x9E37_79B9_7F4A_7C15), 1, 0, BOUND_EXACT, Move::NULL, 0);
+        }
+        assert!(table.hashfull() > 900);
+        table.resize(2);
+        assert_eq!(table.hashfull(), 0);
+    }
+}
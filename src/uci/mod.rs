@@ -0,0 +1,274 @@
+pub const ENGINE_NAME: &str = "TSMChess";
+pub const ENGINE_AUTHOR: &str = "TSM Studios";
+
+pub enum OptionKind {
+    Check { default: bool },
+    Spin { default: i64, min: i64, max: i64 },
+    Button,
+}
+
+pub struct UciOption {
+    pub name: &'static str,
+    pub kind: OptionKind,
+}
+
+// Every option the engine advertises after "uci"
+pub const OPTIONS: [UciOption; 8] = [
+    UciOption { name: "Hash", kind: OptionKind::Spin { default: 16, min: 1, max: 4096 } },
+    UciOption { name: "Clear Hash", kind: OptionKind::Button },
+    UciOption { name: "Ponder", kind: OptionKind::Check { default: false } },
+    UciOption { name: "Move Overhead", kind: OptionKind::Spin { default: 10, min: 0, max: 5000 } },
+    UciOption { name: "Null Move Reduction", kind: OptionKind::Spin { default: 3, min: 0, max: 6 } },
+    UciOption { name: "Null Move Divisor", kind: OptionKind::Spin { default: 6, min: 1, max: 16 } },
+    UciOption { name: "LMR Base", kind: OptionKind::Spin { default: 75, min: 0, max: 300 } },
+    UciOption { name: "LMR Divisor", kind: OptionKind::Spin { default: 225, min: 100, max: 600 } },
+];
+
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub hash: usize, // Transposition table size in MB
+    pub ponder: bool,
+    pub move_overhead: u64, // Milliseconds lost per move to the GUI and the connection
+    pub null_move_reduction: u32, // Base depth reduction of the null move search, 0 turns null-move pruning off
+    pub null_move_divisor: u32, // One more ply of reduction every this many plies of depth
+    pub lmr_base: u32, // Late move reductions in hundredths of a ply, see search::pruning
+    pub lmr_divisor: u32,
+}
+
+impl Options {
+    pub fn new() -> Options {
+        return Options {
+            hash: 16,
+            ponder: false,
+            move_overhead: 10,
+            null_move_reduction: 3,
+            null_move_divisor: 6,
+            lmr_base: 75,
+            lmr_divisor: 225,
+        };
+    }
+
+    // Applies "setoption name <id> [value <x>]", option names are case-insensitive per the UCI spec.
+    // Returns the option's canonical name so the caller can act on buttons and resizes.
+    pub fn set(&mut self, name: &str, value: &str) -> Result<&'static str, String> {
+        let option: &UciOption = match(OPTIONS.iter().find(|option| option.name.eq_ignore_ascii_case(name))) {
+            Some(v) => v,
+            None => return Err(format!("No such option: {}", name)),
+        };
+        match(option.kind) {
+            OptionKind::Check { .. } => {
+                let parsed: bool = match(value.to_ascii_lowercase().as_str()) {
+                    "true" => true,
+                    "false" => false,
+                    _ => return Err(format!("Invalid value for {}: {}", option.name, value)),
+                };
+                if(option.name == "Ponder") {
+                    self.ponder = parsed;
+                }
+            }
+            OptionKind::Spin { min, max, .. } => {
+                let parsed: i64 = match(value.parse::<i64>()) {
+                    Ok(v) => v.clamp(min, max),
+                    Err(_) => return Err(format!("Invalid value for {}: {}", option.name, value)),
+                };
+                match(option.name) {
+                    "Hash" => self.hash = parsed as usize,
+                    "Move Overhead" => self.move_overhead = parsed as u64,
+                    "Null Move Reduction" => self.null_move_reduction = parsed as u32,
+                    "Null Move Divisor" => self.null_move_divisor = parsed as u32,
+                    "LMR Base" => self.lmr_base = parsed as u32,
+                    "LMR Divisor" => self.lmr_divisor = parsed as u32,
+                    _ => (),
+                }
+            }
+            OptionKind::Button => (),
+        }
+        return Ok(option.name);
+    }
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        return Options::new();
+    }
+}
+
+pub fn print_id() {
+    println!("id name {}", ENGINE_NAME);
+    println!("id author {}", ENGINE_AUTHOR);
+    println!();
+    for option in OPTIONS.iter() {
+        match(option.kind) {
+            OptionKind::Check { default } => {
+                println!("option name {} type check default {}", option.name, default);
+            }
+            OptionKind::Spin { default, min, max } => {
+                println!("option name {} type spin default {} min {} max {}", option.name, default, min, max);
+            }
+            OptionKind::Button => {
+                println!("option name {} type button", option.name);
+            }
+        }
+    }
+    println!("uciok");
+}
+
+// Splits "setoption name <id> [value <x>]" into (id, x), both of which may contain spaces
+pub fn parse_setoption(tokens: &[String]) -> Option<(String, String)> {
+    let name_index: usize = tokens.iter().position(|token| token == "name")?;
+    let value_index: Option<usize> = tokens.iter().position(|token| token == "value");
+    let name_end: usize = match(value_index) {
+        Some(v) if v > name_index => v,
+        _ => tokens.len(),
+    };
+    let name: String = tokens[(name_index + 1)..name_end].join(" ");
+    let value: String = match(value_index) {
+        Some(v) if v > name_index => tokens[(v + 1)..].join(" "),
+        _ => String::new(),
+    };
+    if(name.is_empty()) {
+        return None;
+    }
+    return Some((name, value));
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct GoParams {
+    pub wtime: Option<u64>,
+    pub btime: Option<u64>,
+    pub winc: Option<u64>,
+    pub binc: Option<u64>,
+    pub movestogo: Option<u32>,
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    pub mate: Option<u32>,
+    pub movetime: Option<u64>,
+    pub infinite: bool,
+    pub ponder: bool,
+    pub searchmoves: Vec<String>,
+    pub perft: Option<u32>,
+}
+
+impl GoParams {
+    pub fn parse(tokens: &[String]) -> GoParams {
+        let mut params: GoParams = GoParams::default();
+        let mut i: usize = 0;
+        while(i < tokens.len()) {
+            let next: Option<&String> = tokens.get(i + 1);
+            match(tokens[i].as_str()) {
+                "wtime" => params.wtime = parse_clock(next),
+                "btime" => params.btime = parse_clock(next),
+                "winc" => params.winc = parse_clock(next),
+                "binc" => params.binc = parse_clock(next),
+                "movestogo" => params.movestogo = parse_value(next),
+                "depth" => params.depth = parse_value(next),
+                "nodes" => params.nodes = parse_value(next),
+                "mate" => params.mate = parse_value(next),
+                "movetime" => params.movetime = parse_value(next),
+                "infinite" => params.infinite = true,
+                "ponder" => params.ponder = true,
+                "perft" => params.perft = parse_value(next),
+                "searchmoves" => {
+                    // searchmoves swallows every following token that isn't another go keyword
+                    while(i + 1 < tokens.len() && !is_go_keyword(&tokens[i + 1])) {
+                        params.searchmoves.push(tokens[i + 1].clone());
+                        i += 1;
+                    }
+                }
+                _ => (),
+            }
+            i += 1;
+        }
+        return params;
+    }
+}
+
+fn parse_value<T: std::str::FromStr>(token: Option<&String>) -> Option<T> {
+    return token?.parse::<T>().ok();
+}
+
+// GUIs send negative clock values once a player is past the flag, those mean no time left rather than no clock
+fn parse_clock(token: Option<&String>) -> Option<u64> {
+    return parse_value::<i64>(token).map(|milliseconds| milliseconds.max(0) as u64);
+}
+
+fn is_go_keyword(token: &str) -> bool {
+    return matches!(
+        token,
+        "wtime" | "btime" | "winc" | "binc" | "movestogo" | "depth" | "nodes" | "mate" | "movetime" | "infinite" | "ponder" | "searchmoves" | "perft"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(command: &str) -> Vec<String> {
+        return command.split_whitespace().map(|token| token.to_string()).collect::<Vec<String>>();
+    }
+
+    #[test]
+    fn parses_setoption() {
+        let cases: [(&str, Option<(&str, &str)>); 7] = [
+            ("name Clear Hash", Some(("Clear Hash", ""))),
+            ("name Hash value 64", Some(("Hash", "64"))),
+            ("name Move Overhead value 100", Some(("Move Overhead", "100"))),
+            ("name Null Move Reduction value 2", Some(("Null Move Reduction", "2"))),
+            ("name", None),
+            ("name value 5", None),
+            ("value 5", None),
+        ];
+        for (command, expected) in cases {
+            let parsed: Option<(String, String)> = parse_setoption(&tokens(command));
+            assert_eq!(parsed.as_ref().map(|(name, value)| (name.as_str(), value.as_str())), expected, "{}", command);
+        }
+    }
+
+    #[test]
+    fn sets_options() {
+        let mut options: Options = Options::new();
+        assert_eq!(options.set("Hash", "64"), Ok("Hash"));
+        assert_eq!(options.hash, 64);
+        // Names are case-insensitive and out-of-range values are clamped
+        assert_eq!(options.set("move overhead", "99999"), Ok("Move Overhead"));
+        assert_eq!(options.move_overhead, 5000);
+        options.set("Hash", "0").unwrap();
+        assert_eq!(options.hash, 1);
+        options.set("LMR Divisor", "-3").unwrap();
+        assert_eq!(options.lmr_divisor, 100);
+        options.set("Ponder", "TRUE").unwrap();
+        assert!(options.ponder);
+        assert_eq!(options.set("Clear Hash", ""), Ok("Clear Hash"));
+
+        assert!(options.set("Hash", "lots").is_err());
+        assert!(options.set("Hash", "").is_err());
+        assert!(options.set("Ponder", "yes").is_err());
+        assert!(options.set("Threads", "4").is_err());
+        assert_eq!(options.hash, 1);
+    }
+
+    #[test]
+    fn parses_go() {
+        let params: GoParams = GoParams::parse(&tokens("searchmoves e2e4 d2d4 wtime 1000 btime 2000 depth 5"));
+        assert_eq!(params.searchmoves, vec![String::from("e2e4"), String::from("d2d4")]);
+        assert_eq!((params.wtime, params.btime, params.depth), (Some(1000), Some(2000), Some(5)));
+
+        let params: GoParams = GoParams::parse(&tokens("wtime 300 searchmoves g1f3 infinite"));
+        assert_eq!(params.searchmoves, vec![String::from("g1f3")]);
+        assert!(params.infinite);
+
+        // Past the flag the clock goes negative, which means no time left
+        let params: GoParams = GoParams::parse(&tokens("wtime -1500 btime -1 winc -10 binc 20 movestogo 3"));
+        assert_eq!((params.wtime, params.btime, params.winc, params.binc), (Some(0), Some(0), Some(0), Some(20)));
+        assert_eq!(params.movestogo, Some(3));
+
+        let params: GoParams = GoParams::parse(&tokens("ponder movetime 250 nodes 10000 mate 3"));
+        assert!(params.ponder && !params.infinite);
+        assert_eq!((params.movetime, params.nodes, params.mate), (Some(250), Some(10000), Some(3)));
+        // Values that don't parse are dropped rather than taken as the next keyword
+        let params: GoParams = GoParams::parse(&tokens("depth infinite movetime -5"));
+        assert_eq!((params.depth, params.movetime), (None, None));
+        assert!(params.infinite);
+        assert_eq!(GoParams::parse(&tokens("perft 4")).perft, Some(4));
+    }
+}
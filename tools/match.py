@@ -0,0 +1,160 @@
+#!/usr/bin/env python3
+# Self-play match between two TSMChess builds with a sequential probability ratio test on the Elo difference.
+#
+#   python3 tools/match.py NEW BASE [--nodes N | --movetime MS] [--option NAME=VALUE ...]
+#
+# Every opening of the book is played twice, once with each engine as WHITE. Games end on the result the first
+# engine reports after "position" (mate, stalemate, repetition, fifty-move rule, insufficient material), or
+# are adjudicated as draws after --max-plies. The test stops once H1 (NEW is elo1 stronger) or H0 (NEW is at
+# most elo0 stronger) is accepted, or when the book runs out.
+
+import argparse
+import math
+import random
+import subprocess
+
+
+class Engine:
+    def __init__(self, path, options):
+        self.process = subprocess.Popen([path], stdin=subprocess.PIPE, stdout=subprocess.PIPE, text=True, bufsize=1)
+        self.send("uci")
+        self.read_until("uciok")
+        for name, value in options:
+            self.send(f"setoption name {name} value {value}")
+        self.sync()
+
+    def send(self, line):
+        self.process.stdin.write(line + "\n")
+        self.process.stdin.flush()
+
+    def read_until(self, prefix):
+        lines = []
+        while True:
+            line = self.process.stdout.readline()
+            if not line:
+                raise RuntimeError("engine exited")
+            lines.append(line.strip())
+            if line.startswith(prefix):
+                return lines
+
+    def sync(self):
+        self.send("isready")
+        return self.read_until("readyok")
+
+    def quit(self):
+        self.send("quit")
+        self.process.wait()
+
+
+def game_result(referee, moves):
+    referee.send("position startpos moves " + " ".join(moves))
+    for line in referee.sync():
+        if line.startswith("info string Game over:"):
+            return line.split()[4]
+    return None
+
+
+def play_game(referee, white, black, opening, go_command, max_plies):
+    moves = list(opening)
+    for engine in (white, black):
+        engine.send("ucinewgame")
+        engine.sync()
+    while len(moves) < max_plies:
+        result = game_result(referee, moves)
+        if result is not None:
+            return result
+        engine = white if len(moves) % 2 == 0 else black
+        engine.send("position startpos moves " + " ".join(moves))
+        engine.send(go_command)
+        moves.append(engine.read_until("bestmove")[-1].split()[1])
+    return "1/2-1/2"
+
+
+def expected_score(elo):
+    return 1 / (1 + 10 ** (-elo / 400))
+
+
+def elo(score):
+    score = min(max(score, 1e-6), 1 - 1e-6)
+    return -400 * math.log10(1 / score - 1)
+
+
+# Log-likelihood ratio of H1 against H0 under a normal approximation of the per-game score, as in fishtest
+def log_likelihood_ratio(wins, draws, losses, elo0, elo1):
+    games = wins + draws + losses
+    if wins == 0 or losses == 0:
+        return 0.0
+    score = (wins + draws / 2) / games
+    variance = (wins * (1 - score) ** 2 + draws * (0.5 - score) ** 2 + losses * score ** 2) / games
+    score0, score1 = expected_score(elo0), expected_score(elo1)
+    return games * (score1 - score0) * (2 * score - score0 - score1) / (2 * variance)
+
+
+def error_margin(wins, draws, losses):
+    games = wins + draws + losses
+    score = (wins + draws / 2) / games
+    variance = (wins * (1 - score) ** 2 + draws * (0.5 - score) ** 2 + losses * score ** 2) / games
+    deviation = 1.96 * math.sqrt(variance / games)
+    return (elo(score + deviation) - elo(score - deviation)) / 2
+
+
+def main():
+    parser = argparse.ArgumentParser()
+    parser.add_argument("new")
+    parser.add_argument("base")
+    parser.add_argument("--book", default="tools/openings.txt", help="one opening per line, in UCI moves from the start position")
+    parser.add_argument("--nodes", type=int, default=25000)
+    parser.add_argument("--movetime", type=int, help="milliseconds per move, replaces --nodes")
+    parser.add_argument("--option", action="append", default=[], help="NAME=VALUE, sent to both engines")
+    parser.add_argument("--max-plies", type=int, default=400)
+    parser.add_argument("--rounds", type=int, default=1, help="times the book is played through")
+    parser.add_argument("--seed", type=int, default=2026, help="shuffles the book")
+    parser.add_argument("--elo0", type=float, default=0.0)
+    parser.add_argument("--elo1", type=float, default=10.0)
+    parser.add_argument("--alpha", type=float, default=0.05)
+    parser.add_argument("--beta", type=float, default=0.05)
+    args = parser.parse_args()
+
+    options = [tuple(option.split("=", 1)) for option in args.option]
+    go_command = f"go movetime {args.movetime}" if args.movetime else f"go nodes {args.nodes}"
+    with open(args.book) as book:
+        openings = [line.split() for line in book if line.strip()]
+    random.Random(args.seed).shuffle(openings)
+
+    lower_bound = math.log(args.beta / (1 - args.alpha))
+    upper_bound = math.log((1 - args.beta) / args.alpha)
+    referee = Engine(args.new, options)
+    new, base = Engine(args.new, options), Engine(args.base, options)
+    wins = draws = losses = 0
+    verdict = "inconclusive, the book ran out"
+    print(f"{go_command}, options {options}, {len(openings)} openings, SPRT elo0 {args.elo0} elo1 {args.elo1}", flush=True)
+    for opening in openings * args.rounds:
+        for new_is_white in (True, False):
+            white, black = (new, base) if new_is_white else (base, new)
+            result = play_game(referee, white, black, opening, go_command, args.max_plies)
+            if result == "1/2-1/2":
+                draws += 1
+            elif (result == "1-0") == new_is_white:
+                wins += 1
+            else:
+                losses += 1
+        games = wins + draws + losses
+        score = (wins + draws / 2) / games
+        llr = log_likelihood_ratio(wins, draws, losses, args.elo0, args.elo1)
+        print(f"games {games} +{wins} ={draws} -{losses} score {score:.3f} elo {elo(score):+.1f} "
+              f"llr {llr:.2f} ({lower_bound:.2f}, {upper_bound:.2f})", flush=True)
+        if llr >= upper_bound:
+            verdict = "H1 accepted"
+            break
+        if llr <= lower_bound:
+            verdict = "H0 accepted"
+            break
+    games = wins + draws + losses
+    score = (wins + draws / 2) / games
+    print(f"{verdict}: {games} games +{wins} ={draws} -{losses} elo {elo(score):+.1f} +/- {error_margin(wins, draws, losses):.1f}")
+    for engine in (referee, new, base):
+        engine.quit()
+
+
+if __name__ == "__main__":
+    main()